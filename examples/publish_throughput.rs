@@ -0,0 +1,124 @@
+// Measures publish throughput of many concurrent publishers sharing one connection.
+// Requires a running MyServiceBus broker:
+// cargo run --release --example publish_throughput -- 127.0.0.1:6421 64 10000
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use my_service_bus_abstractions::{publisher::MySbMessageSerializer, GetMySbModelTopicId};
+use my_service_bus_tcp_client::{MyServiceBusClient, MyServiceBusSettings};
+use rust_extensions::Logger;
+
+struct BenchSettings {
+    host_port: String,
+}
+
+#[async_trait::async_trait]
+impl MyServiceBusSettings for BenchSettings {
+    async fn get_host_port(&self) -> String {
+        self.host_port.clone()
+    }
+}
+
+struct ConsoleLogger;
+
+impl Logger for ConsoleLogger {
+    fn write_info(&self, process: String, message: String, _ctx: Option<HashMap<String, String>>) {
+        println!("INFO {}: {}", process, message);
+    }
+
+    fn write_warning(
+        &self,
+        process: String,
+        message: String,
+        _ctx: Option<HashMap<String, String>>,
+    ) {
+        println!("WARN {}: {}", process, message);
+    }
+
+    fn write_error(&self, process: String, message: String, _ctx: Option<HashMap<String, String>>) {
+        println!("ERR {}: {}", process, message);
+    }
+
+    fn write_fatal_error(
+        &self,
+        process: String,
+        message: String,
+        _ctx: Option<HashMap<String, String>>,
+    ) {
+        println!("FATAL {}: {}", process, message);
+    }
+}
+
+struct BenchMessage {
+    payload: Vec<u8>,
+}
+
+impl GetMySbModelTopicId for BenchMessage {
+    fn get_topic_id() -> &'static str {
+        "publish-throughput-bench"
+    }
+}
+
+impl MySbMessageSerializer for BenchMessage {
+    fn serialize(
+        &self,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<(Vec<u8>, Option<HashMap<String, String>>), String> {
+        Ok((self.payload.clone(), headers))
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    let host_port = args.get(1).cloned().unwrap_or("127.0.0.1:6421".to_string());
+    let publishers_amount: usize = args.get(2).map(|v| v.parse().unwrap()).unwrap_or(64);
+    let messages_per_publisher: usize = args.get(3).map(|v| v.parse().unwrap()).unwrap_or(10_000);
+
+    let client = MyServiceBusClient::new(
+        "publish-throughput-bench",
+        "0.0.0",
+        Arc::new(BenchSettings { host_port }),
+        Arc::new(ConsoleLogger),
+    );
+
+    client.start().await;
+
+    while !client.has_connection() {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let publisher = Arc::new(client.get_publisher::<BenchMessage>(false).await);
+
+    let started = std::time::Instant::now();
+
+    let mut tasks = Vec::with_capacity(publishers_amount);
+
+    for _ in 0..publishers_amount {
+        let publisher = publisher.clone();
+        tasks.push(tokio::spawn(async move {
+            let message = BenchMessage {
+                payload: vec![0u8; 256],
+            };
+
+            for _ in 0..messages_per_publisher {
+                publisher.publish(&message).await.unwrap();
+            }
+        }));
+    }
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let elapsed = started.elapsed();
+    let total = publishers_amount * messages_per_publisher;
+
+    println!(
+        "Published {} messages by {} publishers in {:?}: {:.0} msg/sec",
+        total,
+        publishers_amount,
+        elapsed,
+        total as f64 / elapsed.as_secs_f64()
+    );
+}
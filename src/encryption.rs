@@ -0,0 +1,286 @@
+use std::{collections::HashMap, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use my_service_bus_abstractions::{publisher::MessageToPublish, MySbMessage};
+use rust_extensions::Logger;
+
+use crate::publishers::PublishInterceptor;
+
+pub const ENCRYPTION_KEY_ID_HEADER: &str = "my-sb-encryption-key-id";
+
+const NONCE_SIZE: usize = 12;
+
+pub trait EncryptionKeyProvider {
+    // None leaves messages of the topic unencrypted
+    fn get_current_key_id(&self, topic_id: &str) -> Option<String>;
+
+    // Rotated keys have to stay available while messages encrypted with them can still be delivered
+    fn get_key(&self, key_id: &str) -> Option<[u8; 32]>;
+}
+
+// Content is stored as nonce followed by the ciphertext. Topic id is authenticated,
+// so a payload can not be replayed to another topic.
+fn encrypt(key: &[u8; 32], topic_id: &str, content: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let encrypted = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: content,
+                aad: topic_id.as_bytes(),
+            },
+        )
+        .map_err(|err| format!("Can not encrypt message: {}", err))?;
+
+    let mut result = Vec::with_capacity(NONCE_SIZE + encrypted.len());
+    result.extend_from_slice(nonce.as_slice());
+    result.extend_from_slice(encrypted.as_slice());
+    Ok(result)
+}
+
+fn decrypt(key: &[u8; 32], topic_id: &str, content: &[u8]) -> Result<Vec<u8>, String> {
+    if content.len() < NONCE_SIZE {
+        return Err("Encrypted content is shorter than nonce".to_string());
+    }
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let (nonce, encrypted) = content.split_at(NONCE_SIZE);
+
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: encrypted,
+                aad: topic_id.as_bytes(),
+            },
+        )
+        .map_err(|err| format!("Can not decrypt message: {}", err))
+}
+
+// Has to be the last interceptor, since content changed after it would break the ciphertext
+pub struct EncryptionPublishInterceptor {
+    key_provider: Arc<dyn EncryptionKeyProvider + Send + Sync + 'static>,
+}
+
+impl EncryptionPublishInterceptor {
+    pub fn new(key_provider: Arc<dyn EncryptionKeyProvider + Send + Sync + 'static>) -> Self {
+        Self { key_provider }
+    }
+}
+
+#[async_trait::async_trait]
+impl PublishInterceptor for EncryptionPublishInterceptor {
+    fn get_name(&self) -> &str {
+        "Encryption"
+    }
+
+    async fn intercept(
+        &self,
+        topic_id: &str,
+        message: &mut MessageToPublish,
+    ) -> Result<(), String> {
+        // Message replayed by a failure handler has already been encrypted
+        if let Some(headers) = message.headers.as_ref() {
+            if headers.contains_key(ENCRYPTION_KEY_ID_HEADER) {
+                return Ok(());
+            }
+        }
+
+        let key_id = match self.key_provider.get_current_key_id(topic_id) {
+            Some(key_id) => key_id,
+            None => return Ok(()),
+        };
+
+        let key = match self.key_provider.get_key(key_id.as_str()) {
+            Some(key) => key,
+            None => return Err(format!("Encryption key {} is not found", key_id)),
+        };
+
+        message.content = encrypt(&key, topic_id, message.content.as_slice())?;
+
+        message
+            .headers
+            .get_or_insert_with(Default::default)
+            .insert(ENCRYPTION_KEY_ID_HEADER.to_string(), key_id);
+
+        Ok(())
+    }
+}
+
+// Message which can not be decrypted is delivered as is and keeps ENCRYPTION_KEY_ID_HEADER,
+// so a subscriber can tell its content is still encrypted
+pub fn decrypt_messages(
+    key_provider: &dyn EncryptionKeyProvider,
+    logger: &dyn Logger,
+    topic_id: &str,
+    messages: &mut [MySbMessage],
+) {
+    for message in messages {
+        let result = decrypt_content(
+            key_provider,
+            topic_id,
+            &mut message.headers,
+            &mut message.content,
+        );
+
+        if let Err((key_id, err)) = result {
+            let mut ctx = HashMap::new();
+            ctx.insert("TopicId".to_string(), topic_id.to_string());
+            ctx.insert("MessageId".to_string(), message.id.to_string());
+            ctx.insert("KeyId".to_string(), key_id);
+
+            logger.write_error("DecryptMessages".to_string(), err, Some(ctx));
+        }
+    }
+}
+
+// Unencrypted content is left as is. Err carries the key id along with the reason.
+fn decrypt_content(
+    key_provider: &dyn EncryptionKeyProvider,
+    topic_id: &str,
+    headers: &mut Option<HashMap<String, String>>,
+    content: &mut Vec<u8>,
+) -> Result<(), (String, String)> {
+    let key_id = match headers.as_ref() {
+        Some(headers) => match headers.get(ENCRYPTION_KEY_ID_HEADER) {
+            Some(key_id) => key_id.clone(),
+            None => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+
+    let result = match key_provider.get_key(key_id.as_str()) {
+        Some(key) => decrypt(&key, topic_id, content.as_slice()),
+        None => Err(format!("Encryption key {} is not found", key_id)),
+    };
+
+    match result {
+        Ok(decrypted) => {
+            *content = decrypted;
+
+            if let Some(headers) = headers.as_mut() {
+                headers.remove(ENCRYPTION_KEY_ID_HEADER);
+            }
+
+            Ok(())
+        }
+        Err(err) => Err((key_id, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use my_service_bus_abstractions::publisher::MessageToPublish;
+
+    use super::{
+        decrypt, decrypt_content, encrypt, EncryptionKeyProvider, EncryptionPublishInterceptor,
+        ENCRYPTION_KEY_ID_HEADER, NONCE_SIZE,
+    };
+    use crate::publishers::PublishInterceptor;
+
+    const KEY: [u8; 32] = [7; 32];
+    const OTHER_KEY: [u8; 32] = [9; 32];
+
+    struct TestKeyProvider;
+
+    impl EncryptionKeyProvider for TestKeyProvider {
+        fn get_current_key_id(&self, _topic_id: &str) -> Option<String> {
+            Some("key-1".to_string())
+        }
+
+        fn get_key(&self, key_id: &str) -> Option<[u8; 32]> {
+            match key_id {
+                "key-1" => Some(KEY),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let encrypted = encrypt(&KEY, "topic", b"content").unwrap();
+
+        assert_ne!(b"content".to_vec(), encrypted);
+        assert_eq!(
+            b"content".to_vec(),
+            decrypt(&KEY, "topic", &encrypted).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_other_topic_fails() {
+        let encrypted = encrypt(&KEY, "topic", b"content").unwrap();
+
+        assert!(decrypt(&KEY, "other-topic", &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let encrypted = encrypt(&KEY, "topic", b"content").unwrap();
+
+        assert!(decrypt(&OTHER_KEY, "topic", &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_content_shorter_than_nonce_fails() {
+        assert!(decrypt(&KEY, "topic", &[0; NONCE_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn test_key_id_header_is_kept_on_failure() {
+        let mut headers = HashMap::new();
+        headers.insert(ENCRYPTION_KEY_ID_HEADER.to_string(), "key-1".to_string());
+        let mut headers = Some(headers);
+
+        let encrypted = encrypt(&KEY, "topic", b"content").unwrap();
+        let mut content = encrypted.clone();
+
+        let result = decrypt_content(&TestKeyProvider, "other-topic", &mut headers, &mut content);
+
+        assert_eq!("key-1", result.unwrap_err().0);
+        assert_eq!(encrypted, content);
+        assert!(headers.unwrap().contains_key(ENCRYPTION_KEY_ID_HEADER));
+    }
+
+    #[test]
+    fn test_key_id_header_is_removed_on_success() {
+        let mut headers = HashMap::new();
+        headers.insert(ENCRYPTION_KEY_ID_HEADER.to_string(), "key-1".to_string());
+        let mut headers = Some(headers);
+
+        let mut content = encrypt(&KEY, "topic", b"content").unwrap();
+
+        decrypt_content(&TestKeyProvider, "topic", &mut headers, &mut content).unwrap();
+
+        assert_eq!(b"content".to_vec(), content);
+        assert!(!headers.unwrap().contains_key(ENCRYPTION_KEY_ID_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_message_is_not_encrypted_again() {
+        let interceptor = EncryptionPublishInterceptor::new(std::sync::Arc::new(TestKeyProvider));
+
+        let mut message = MessageToPublish {
+            headers: None,
+            content: b"content".to_vec(),
+        };
+
+        interceptor.intercept("topic", &mut message).await.unwrap();
+        let encrypted = message.content.clone();
+
+        interceptor.intercept("topic", &mut message).await.unwrap();
+
+        assert_eq!(encrypted, message.content);
+        assert_eq!(
+            b"content".to_vec(),
+            decrypt(&KEY, "topic", &message.content).unwrap()
+        );
+    }
+}
@@ -1,11 +1,41 @@
+#[cfg(feature = "encryption")]
+mod encryption;
 mod my_sb_client;
 mod new_connection_handler;
 mod publishers;
 mod settings;
 mod subscribers;
 mod tcp_client_data;
+#[cfg(feature = "opentelemetry")]
+mod trace_context;
 pub use my_sb_client::*;
 pub use settings::MyServiceBusSettings;
+pub use subscribers::{
+    MessageHandler, RawMessageHandler, RawMessagesReader, RawSubscriberCallback, SubscribeError,
+    SubscribeOptions, UnsubscribeError, UnsubscribeOptions, UnsubscribeOutcome,
+    PARKED_BATCH_TIMEOUT,
+};
+
+pub use publishers::{
+    is_in_flight_window_full, is_rate_limit_exceeded, BoundedPublishQueue,
+    BoundedPublishQueueSettings, CancellablePublishError, ChunkRetryMode, CreateTopicError,
+    CreateTopicOptions, FilePublishFailureHandler, FileScheduledMessagesStore,
+    InFlightWindowFullBehavior, InFlightWindowSettings, InFlightWindowStats,
+    LatencyHistogramSnapshot, LoggerPublishFailureHandler, MaxMessageSizeValidator,
+    MessageValidator, MySbRawMessage, PublishChunkError, PublishChunkSettings,
+    PublishFailureHandler, PublishInterceptor, PublishQueueDepth, PublishRateLimit,
+    PublisherSettings, PublisherSettingsError, QueueOverflowPolicy, RateLimitExceededBehavior,
+    RequiredHeadersValidator, ScheduledMessage, ScheduledMessagesStore,
+    TopicPublishMetricsSnapshot, IN_FLIGHT_WINDOW_FULL, LATENCY_BUCKETS_MICROS, MESSAGE_ID_HEADER,
+    PERSIST_IMMEDIATELY_HEADER, PRODUCER_APP_HEADER, PRODUCER_INSTANCE_HEADER, PUBLISHED_AT_HEADER,
+    RATE_LIMIT_EXCEEDED,
+};
 
 pub use my_sb_client::MyServiceBusClient;
 use tcp_client_data::*;
+pub use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "encryption")]
+pub use encryption::{
+    EncryptionKeyProvider, EncryptionPublishInterceptor, ENCRYPTION_KEY_ID_HEADER,
+};
@@ -1,21 +1,35 @@
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::publishers::MySbPublishers;
-use crate::subscribers::MySbSubscribers;
+use chrono::{DateTime, Utc};
+
+use crate::publishers::{
+    BoundedPublishQueue, BoundedPublishQueueSettings, CancellablePublishError, ChunkRetryMode,
+    CreateTopicError, CreateTopicOptions, InFlightWindowSettings, InFlightWindowStats,
+    MessageValidator, MySbPublishers, MySbRawMessage, PublishChunkError, PublishChunkSettings,
+    PublishFailureHandler, PublishInterceptor, PublishScheduler, PublisherSettings,
+    PublisherSettingsError, ScheduledMessagesStore, TopicPublishMetricsSnapshot,
+};
+use crate::subscribers::{
+    DeserializingMessageHandler, MessageHandler, MySbSubscribers, ParallelSubscriber,
+    RawMessageHandler, RawSubscriber, RawSubscriberCallback, SubscribeError, SubscribeOptions,
+    UnsubscribeError, UnsubscribeOptions, UnsubscribeOutcome,
+};
 
 use crate::TcpClientData;
 use my_service_bus_abstractions::publisher::{
-    MySbMessageSerializer, MyServiceBusPublisher, PublisherWithInternalQueue,
+    MessageToPublish, MySbMessageSerializer, MyServiceBusPublisher, PublisherWithInternalQueue,
 };
 use my_service_bus_abstractions::subscriber::MySbMessageDeserializer;
 use my_service_bus_abstractions::subscriber::Subscriber;
 use my_service_bus_abstractions::subscriber::SubscriberCallback;
 use my_service_bus_abstractions::subscriber::TopicQueueType;
-use my_service_bus_abstractions::GetMySbModelTopicId;
+use my_service_bus_abstractions::{GetMySbModelTopicId, PublishError};
 use my_service_bus_tcp_shared::MySbTcpSerializer;
 use my_tcp_sockets::{TcpClient, TcpClientSocketSettings};
 use rust_extensions::{Logger, StrOrString};
+use tokio_util::sync::CancellationToken;
 
 use super::MyServiceBusSettings;
 
@@ -52,12 +66,15 @@ impl MyServiceBusClient {
     ) -> Self {
         let tcp_settings = TcpConnectionSettings::new(settings);
 
+        let app_name: StrOrString<'static> = app_name.into();
+
         let data = TcpClientData {
-            publishers: Arc::new(MySbPublishers::new()),
+            publishers: Arc::new(MySbPublishers::new(app_name.to_string())),
+            scheduler: Arc::new(PublishScheduler::new(logger.clone())),
             subscribers: Arc::new(MySbSubscribers::new()),
             logger,
             has_connection: Arc::new(AtomicBool::new(false)),
-            app_name: app_name.into(),
+            app_name,
             app_version: app_version.into(),
             client_version: get_client_version(),
         };
@@ -69,6 +86,12 @@ impl MyServiceBusClient {
     }
 
     pub async fn start(&self) {
+        self.data
+            .scheduler
+            .clone()
+            .start(self.data.publishers.clone())
+            .await;
+
         self.tcp_client
             .start(
                 Arc::new(|| -> MySbTcpSerializer {
@@ -115,6 +138,329 @@ impl MyServiceBusClient {
         )
     }
 
+    pub async fn get_bounded_publisher<TModel: MySbMessageSerializer + GetMySbModelTopicId>(
+        &self,
+        settings: BoundedPublishQueueSettings,
+    ) -> BoundedPublishQueue<TModel> {
+        let topic_id = TModel::get_topic_id();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+        BoundedPublishQueue::new(
+            topic_id.to_string(),
+            settings,
+            self.data.publishers.clone(),
+            self.data.logger.clone(),
+        )
+    }
+
+    pub async fn get_raw_publisher(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        do_retries: bool,
+    ) -> MyServiceBusPublisher<MySbRawMessage> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+        MyServiceBusPublisher::new(
+            topic_id.to_string(),
+            self.data.publishers.clone(),
+            do_retries,
+            self.data.logger.clone(),
+        )
+    }
+
+    pub async fn get_raw_publisher_with_internal_queue(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+    ) -> PublisherWithInternalQueue<MySbRawMessage> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+        PublisherWithInternalQueue::new(
+            topic_id.to_string(),
+            self.data.publishers.clone(),
+            self.data.logger.clone(),
+        )
+    }
+
+    pub async fn get_raw_bounded_publisher(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        settings: BoundedPublishQueueSettings,
+    ) -> BoundedPublishQueue<MySbRawMessage> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+        BoundedPublishQueue::new(
+            topic_id.to_string(),
+            settings,
+            self.data.publishers.clone(),
+            self.data.logger.clone(),
+        )
+    }
+
+    pub async fn create_topic(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        options: CreateTopicOptions,
+    ) -> Result<(), CreateTopicError> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        self.data
+            .publishers
+            .create_topic(topic_id.as_str(), &options)
+            .await
+    }
+
+    pub async fn set_publish_chunk_settings(&self, settings: PublishChunkSettings) {
+        self.data
+            .publishers
+            .set_publish_chunk_settings(settings)
+            .await;
+    }
+
+    pub async fn publish_messages_in_chunks(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        do_retries: bool,
+        retry_mode: ChunkRetryMode,
+    ) -> Result<(), PublishChunkError> {
+        self.data
+            .publishers
+            .publish_messages_in_chunks(topic_id, messages, do_retries, retry_mode)
+            .await
+    }
+
+    pub async fn publish_multi(
+        &self,
+        batches: &[(&str, &[MessageToPublish])],
+        do_retries: bool,
+    ) -> Vec<(String, Result<(), PublishError>)> {
+        for (topic_id, _) in batches {
+            self.data
+                .publishers
+                .create_topic_if_not_exists(topic_id.to_string())
+                .await;
+        }
+
+        self.data
+            .publishers
+            .publish_multi(batches, do_retries)
+            .await
+    }
+
+    pub async fn publish_with_cancellation<TModel: MySbMessageSerializer + GetMySbModelTopicId>(
+        &self,
+        messages: &[TModel],
+        do_retries: bool,
+        cancellation_token: &CancellationToken,
+    ) -> Result<(), CancellablePublishError> {
+        let topic_id = TModel::get_topic_id();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+
+        let mut to_publish = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            let (content, headers) = message
+                .serialize(None)
+                .map_err(PublishError::SerializationError)?;
+            to_publish.push(MessageToPublish { headers, content });
+        }
+
+        self.publish_raw_with_cancellation(topic_id, &to_publish, do_retries, cancellation_token)
+            .await
+    }
+
+    pub async fn publish_raw_with_cancellation(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        do_retries: bool,
+        cancellation_token: &CancellationToken,
+    ) -> Result<(), CancellablePublishError> {
+        self.data
+            .publishers
+            .publish_messages_with_cancellation(topic_id, messages, do_retries, cancellation_token)
+            .await
+    }
+
+    pub fn set_scheduled_messages_store(
+        &self,
+        store: Arc<dyn ScheduledMessagesStore + Send + Sync + 'static>,
+    ) {
+        self.data.scheduler.set_store(store);
+    }
+
+    pub async fn publish_at<TModel: MySbMessageSerializer + GetMySbModelTopicId>(
+        &self,
+        message: &TModel,
+        publish_at: DateTime<Utc>,
+    ) -> Result<String, PublishError> {
+        let (content, headers) = message
+            .serialize(None)
+            .map_err(PublishError::SerializationError)?;
+
+        let topic_id = TModel::get_topic_id();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+
+        let id = self
+            .data
+            .scheduler
+            .schedule(
+                topic_id.to_string(),
+                MessageToPublish { headers, content },
+                publish_at,
+            )
+            .await;
+
+        Ok(id)
+    }
+
+    pub async fn publish_after<TModel: MySbMessageSerializer + GetMySbModelTopicId>(
+        &self,
+        message: &TModel,
+        delay: Duration,
+    ) -> Result<String, PublishError> {
+        let publish_at = chrono::Duration::from_std(delay)
+            .ok()
+            .and_then(|delay| Utc::now().checked_add_signed(delay));
+
+        match publish_at {
+            Some(publish_at) => self.publish_at(message, publish_at).await,
+            None => Err(PublishError::Other(format!(
+                "Publish delay {:?} is out of range",
+                delay
+            ))),
+        }
+    }
+
+    pub fn get_scheduled_messages_amount(&self) -> usize {
+        self.data.scheduler.get_scheduled_amount()
+    }
+
+    pub async fn set_publish_failure_handler(
+        &self,
+        handler: Arc<dyn PublishFailureHandler + Send + Sync + 'static>,
+    ) {
+        self.data.publishers.set_failure_handler(handler).await;
+    }
+
+    pub async fn add_publish_interceptor(
+        &self,
+        interceptor: Arc<dyn PublishInterceptor + Send + Sync + 'static>,
+    ) {
+        self.data.publishers.add_interceptor(interceptor).await;
+    }
+
+    pub async fn add_message_validator<TModel: MySbMessageSerializer + GetMySbModelTopicId>(
+        &self,
+        validator: Arc<dyn MessageValidator + Send + Sync + 'static>,
+    ) {
+        let topic_id = TModel::get_topic_id();
+        self.add_topic_message_validator(topic_id, validator).await;
+    }
+
+    pub async fn add_topic_message_validator(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        validator: Arc<dyn MessageValidator + Send + Sync + 'static>,
+    ) {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        self.data
+            .publishers
+            .add_validator(topic_id.to_string(), validator)
+            .await;
+    }
+
+    // Encryption interceptor is added at this point, so interceptors which change content
+    // have to be added before it
+    #[cfg(feature = "encryption")]
+    pub async fn set_encryption_key_provider(
+        &self,
+        key_provider: Arc<dyn crate::encryption::EncryptionKeyProvider + Send + Sync + 'static>,
+    ) {
+        self.data
+            .subscribers
+            .set_key_provider(key_provider.clone(), self.data.logger.clone());
+
+        self.data
+            .publishers
+            .add_interceptor(Arc::new(
+                crate::encryption::EncryptionPublishInterceptor::new(key_provider),
+            ))
+            .await;
+    }
+
+    pub async fn publish_fire_and_forget<TModel: MySbMessageSerializer + GetMySbModelTopicId>(
+        &self,
+        messages: &[TModel],
+    ) -> Result<(), PublishError> {
+        let topic_id = TModel::get_topic_id();
+        self.data
+            .publishers
+            .create_topic_if_not_exists(topic_id.to_string())
+            .await;
+
+        let mut to_publish = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            let (content, headers) = message
+                .serialize(None)
+                .map_err(PublishError::SerializationError)?;
+            to_publish.push(MessageToPublish { headers, content });
+        }
+
+        self.data
+            .publishers
+            .publish_fire_and_forget(topic_id, &to_publish)
+            .await
+    }
+
+    pub async fn publish_raw_fire_and_forget(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+    ) -> Result<(), PublishError> {
+        self.data
+            .publishers
+            .publish_fire_and_forget(topic_id, messages)
+            .await
+    }
+
+    pub async fn set_publisher_settings<TModel: GetMySbModelTopicId>(
+        &self,
+        settings: PublisherSettings,
+    ) -> Result<(), PublisherSettingsError> {
+        let topic_id = TModel::get_topic_id();
+        self.set_topic_publisher_settings(topic_id, settings).await
+    }
+
+    pub async fn set_topic_publisher_settings(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        settings: PublisherSettings,
+    ) -> Result<(), PublisherSettingsError> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        self.data
+            .publishers
+            .set_publisher_settings(topic_id.to_string(), settings)
+            .await
+    }
+
     pub async fn subscribe<
         TModel: GetMySbModelTopicId + MySbMessageDeserializer<Item = TModel> + Send + Sync + 'static,
     >(
@@ -122,10 +468,25 @@ impl MyServiceBusClient {
         queue_id: impl Into<StrOrString<'static>>,
         queue_type: TopicQueueType,
         callback: Arc<dyn SubscriberCallback<TModel> + Send + Sync + 'static>,
-    ) {
+    ) -> Result<(), SubscribeError> {
+        self.subscribe_with_options(queue_id, queue_type, callback, SubscribeOptions::default())
+            .await
+    }
+
+    pub async fn subscribe_with_options<
+        TModel: GetMySbModelTopicId + MySbMessageDeserializer<Item = TModel> + Send + Sync + 'static,
+    >(
+        &self,
+        queue_id: impl Into<StrOrString<'static>>,
+        queue_type: TopicQueueType,
+        callback: Arc<dyn SubscriberCallback<TModel> + Send + Sync + 'static>,
+        options: SubscribeOptions,
+    ) -> Result<(), SubscribeError> {
         let topic_id = TModel::get_topic_id();
         let queue_id: StrOrString<'static> = queue_id.into();
 
+        check_max_parallelism(topic_id, queue_id.as_str(), &options)?;
+
         let subscriber: Subscriber<TModel> = Subscriber::new(
             topic_id.into(),
             queue_id.clone(),
@@ -138,8 +499,157 @@ impl MyServiceBusClient {
         let subscriber = Arc::new(subscriber);
         self.data
             .subscribers
-            .add(topic_id, queue_id.to_string(), subscriber)
-            .await;
+            .add(
+                topic_id.to_string(),
+                queue_id.to_string(),
+                subscriber,
+                &options,
+            )
+            .await
+    }
+
+    pub async fn subscribe_raw(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        queue_id: impl Into<StrOrString<'static>>,
+        queue_type: TopicQueueType,
+        callback: Arc<dyn RawSubscriberCallback + Send + Sync + 'static>,
+    ) -> Result<(), SubscribeError> {
+        self.subscribe_raw_with_options(
+            topic_id,
+            queue_id,
+            queue_type,
+            callback,
+            SubscribeOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn subscribe_raw_with_options(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        queue_id: impl Into<StrOrString<'static>>,
+        queue_type: TopicQueueType,
+        callback: Arc<dyn RawSubscriberCallback + Send + Sync + 'static>,
+        options: SubscribeOptions,
+    ) -> Result<(), SubscribeError> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        let queue_id: StrOrString<'static> = queue_id.into();
+
+        check_max_parallelism(topic_id.as_str(), queue_id.as_str(), &options)?;
+
+        let subscriber = RawSubscriber::new(
+            topic_id.to_string(),
+            queue_id.to_string(),
+            queue_type,
+            callback,
+            self.data.logger.clone(),
+            self.data.subscribers.clone(),
+        );
+
+        self.data
+            .subscribers
+            .add(
+                topic_id.to_string(),
+                queue_id.to_string(),
+                Arc::new(subscriber),
+                &options,
+            )
+            .await
+    }
+
+    // Messages of a delivered batch are handled one by one with up to options.max_parallelism at a time
+    pub async fn subscribe_parallel<
+        TModel: GetMySbModelTopicId + MySbMessageDeserializer<Item = TModel> + Send + Sync + 'static,
+    >(
+        &self,
+        queue_id: impl Into<StrOrString<'static>>,
+        queue_type: TopicQueueType,
+        handler: Arc<dyn MessageHandler<TModel> + Send + Sync + 'static>,
+        options: SubscribeOptions,
+    ) -> Result<(), SubscribeError> {
+        self.subscribe_raw_parallel(
+            TModel::get_topic_id(),
+            queue_id,
+            queue_type,
+            Arc::new(DeserializingMessageHandler::new(handler)),
+            options,
+        )
+        .await
+    }
+
+    pub async fn subscribe_raw_parallel(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        queue_id: impl Into<StrOrString<'static>>,
+        queue_type: TopicQueueType,
+        handler: Arc<dyn RawMessageHandler + Send + Sync + 'static>,
+        options: SubscribeOptions,
+    ) -> Result<(), SubscribeError> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        let queue_id: StrOrString<'static> = queue_id.into();
+
+        let subscriber = ParallelSubscriber::new(
+            topic_id.to_string(),
+            queue_id.to_string(),
+            queue_type,
+            options.max_parallelism.unwrap_or(1),
+            handler,
+            self.data.logger.clone(),
+            self.data.subscribers.clone(),
+        );
+
+        self.data
+            .subscribers
+            .add(
+                topic_id.to_string(),
+                queue_id.to_string(),
+                Arc::new(subscriber),
+                &options,
+            )
+            .await
+    }
+
+    // Protocol has no unsubscribe packet. By default the broker keeps the subscription till the next reconnect
+    // and batches it still delivers are returned to the queue as failed after PARKED_BATCH_TIMEOUT.
+    // UnsubscribeOptions::reconnect stops the delivery right away.
+    pub async fn unsubscribe(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        queue_id: impl Into<StrOrString<'static>>,
+    ) -> Result<UnsubscribeOutcome, UnsubscribeError> {
+        self.unsubscribe_with_options(topic_id, queue_id, UnsubscribeOptions::default())
+            .await
+    }
+
+    pub async fn unsubscribe_with_options(
+        &self,
+        topic_id: impl Into<StrOrString<'static>>,
+        queue_id: impl Into<StrOrString<'static>>,
+        options: UnsubscribeOptions,
+    ) -> Result<UnsubscribeOutcome, UnsubscribeError> {
+        let topic_id: StrOrString<'static> = topic_id.into();
+        let queue_id: StrOrString<'static> = queue_id.into();
+        self.data
+            .subscribers
+            .unsubscribe(topic_id.as_str(), queue_id.as_str(), &options)
+            .await
+    }
+
+    pub async fn set_in_flight_window(&self, settings: InFlightWindowSettings) {
+        self.data.publishers.set_in_flight_window(settings).await;
+    }
+
+    pub async fn set_resend_on_reconnect(&self, value: bool) {
+        self.data.publishers.set_resend_on_reconnect(value).await;
+    }
+
+    pub fn get_in_flight_window_stats(&self) -> InFlightWindowStats {
+        self.data.publishers.get_in_flight_window_stats()
+    }
+
+    pub fn get_publish_metrics(&self) -> Vec<TopicPublishMetricsSnapshot> {
+        self.data.publishers.get_publish_metrics()
     }
 
     pub fn has_connection(&self) -> bool {
@@ -152,3 +662,20 @@ impl MyServiceBusClient {
 fn get_client_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
+
+// Batch callbacks get the whole batch and confirm it themselves, so its messages can not be handed out concurrently
+fn check_max_parallelism(
+    topic_id: &str,
+    queue_id: &str,
+    options: &SubscribeOptions,
+) -> Result<(), SubscribeError> {
+    match options.max_parallelism {
+        Some(max_parallelism) if max_parallelism > 1 => {
+            Err(SubscribeError::MaxParallelismNotSupported {
+                topic_id: topic_id.to_string(),
+                queue_id: queue_id.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
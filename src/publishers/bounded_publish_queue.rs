@@ -0,0 +1,684 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use my_service_bus_abstractions::{
+    publisher::{MessageToPublish, MySbMessageSerializer},
+    MyServiceBusPublisherClient, PublishError,
+};
+use rust_extensions::Logger;
+use tokio::sync::Notify;
+
+use super::{
+    append_messages_record, get_message_size, in_flight_window, serialize_messages_record,
+    MessagesRecordsReader, MySbPublishers,
+};
+
+#[derive(Debug, Clone)]
+pub enum QueueOverflowPolicy {
+    DropOldest,
+    DropNewest,
+    Block,
+    SpillToDisk(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct BoundedPublishQueueSettings {
+    pub max_messages: usize,
+    pub max_bytes: usize,
+    pub overflow_policy: QueueOverflowPolicy,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PublishQueueDepth {
+    pub messages: usize,
+    pub bytes: usize,
+    pub spilled_messages: usize,
+    pub dropped_messages: u64,
+}
+
+struct QueueState {
+    queued: VecDeque<MessageToPublish>,
+    // Batch which is being published still holds its room in the queue
+    publishing_messages: usize,
+    bytes: usize,
+    spilled_messages: usize,
+    // Spill file left by the previous run is not counted yet
+    restoring_spill_file: bool,
+    dropped_messages: u64,
+}
+
+impl QueueState {
+    fn has_room(&self, settings: &BoundedPublishQueueSettings, message_size: usize) -> bool {
+        in_flight_window::has_room(
+            self.queued.len() + self.publishing_messages,
+            self.bytes,
+            message_size,
+            settings.max_messages,
+            settings.max_bytes,
+        )
+    }
+
+    fn push(&mut self, message: MessageToPublish, message_size: usize) {
+        self.bytes += message_size;
+        self.queued.push_back(message);
+    }
+
+    fn drop_oldest(&mut self) -> bool {
+        match self.queued.pop_front() {
+            Some(message) => {
+                self.bytes -= get_message_size(&message);
+                self.dropped_messages += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn get_depth(&self) -> PublishQueueDepth {
+        PublishQueueDepth {
+            messages: self.queued.len() + self.publishing_messages,
+            bytes: self.bytes,
+            spilled_messages: self.spilled_messages,
+            dropped_messages: self.dropped_messages,
+        }
+    }
+}
+
+struct BoundedPublishQueueInner {
+    topic_id: String,
+    settings: BoundedPublishQueueSettings,
+    state: Mutex<QueueState>,
+    // Keeps spilled messages in order with the ones which are put to memory
+    spill_lock: tokio::sync::Mutex<()>,
+    new_messages: Notify,
+    space_released: Notify,
+    closed: AtomicBool,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+impl BoundedPublishQueueInner {
+    fn report_depth(&self, state: &QueueState) {
+        #[cfg(feature = "metrics")]
+        {
+            let depth = state.get_depth();
+            metrics::gauge!("my_sb_publish_queue_messages", depth.messages as f64, "topic" => self.topic_id.clone());
+            metrics::gauge!("my_sb_publish_queue_bytes", depth.bytes as f64, "topic" => self.topic_id.clone());
+            metrics::gauge!("my_sb_publish_queue_spilled_messages", depth.spilled_messages as f64, "topic" => self.topic_id.clone());
+        }
+
+        #[cfg(not(feature = "metrics"))]
+        let _ = state;
+    }
+
+    fn get_spill_path(&self) -> Option<&str> {
+        match &self.settings.overflow_policy {
+            QueueOverflowPolicy::SpillToDisk(path) => Some(path.as_str()),
+            _ => None,
+        }
+    }
+
+    async fn enqueue(&self, message: MessageToPublish) -> Result<(), PublishError> {
+        let message_size = get_message_size(&message);
+
+        if let Some(path) = self.get_spill_path() {
+            let _spill_lock = self.spill_lock.lock().await;
+
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if state.spilled_messages == 0
+                    && !state.restoring_spill_file
+                    && state.has_room(&self.settings, message_size)
+                {
+                    state.push(message, message_size);
+                    self.report_depth(&state);
+                    self.new_messages.notify_one();
+                    return Ok(());
+                }
+            }
+
+            let record = serialize_messages_record(self.topic_id.as_str(), &[message]);
+
+            if let Err(err) = append_messages_record(path, record.as_slice()).await {
+                return Err(PublishError::Other(format!(
+                    "Can not spill message of topic {} to {}: {:?}",
+                    self.topic_id, path, err
+                )));
+            }
+
+            let mut state = self.state.lock().unwrap();
+            state.spilled_messages += 1;
+            self.report_depth(&state);
+            self.new_messages.notify_one();
+            return Ok(());
+        }
+
+        let mut message = Some(message);
+
+        loop {
+            let space_released = self.space_released.notified();
+
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if state.has_room(&self.settings, message_size) {
+                    state.push(message.take().unwrap(), message_size);
+                    self.report_depth(&state);
+                    self.new_messages.notify_one();
+                    return Ok(());
+                }
+
+                match self.settings.overflow_policy {
+                    QueueOverflowPolicy::DropOldest => {
+                        // Only queued messages can be dropped, the batch being published is kept
+                        while !state.has_room(&self.settings, message_size) {
+                            if !state.drop_oldest() {
+                                break;
+                            }
+                        }
+
+                        state.push(message.take().unwrap(), message_size);
+                        self.report_depth(&state);
+                        self.new_messages.notify_one();
+                        return Ok(());
+                    }
+                    QueueOverflowPolicy::DropNewest => {
+                        state.dropped_messages += 1;
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+
+            space_released.await;
+        }
+    }
+
+    fn take_batch(&self) -> Vec<MessageToPublish> {
+        let mut state = self.state.lock().unwrap();
+        let result: Vec<MessageToPublish> = state.queued.drain(..).collect();
+        state.publishing_messages = result.len();
+        result
+    }
+
+    fn release_batch(&self, batch: &[MessageToPublish]) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.publishing_messages = 0;
+            state.bytes -= batch.iter().map(get_message_size).sum::<usize>();
+            self.report_depth(&state);
+        }
+
+        self.space_released.notify_waiters();
+    }
+
+    fn has_spilled(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.spilled_messages > 0
+    }
+
+    fn spilled_published(&self, messages_amount: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.spilled_messages = state.spilled_messages.saturating_sub(messages_amount);
+        self.report_depth(&state);
+    }
+
+    fn write_error(&self, message: String) {
+        let mut ctx = HashMap::new();
+        ctx.insert("TopicId".to_string(), self.topic_id.clone());
+
+        self.logger
+            .write_error("BoundedPublishQueue".to_string(), message, Some(ctx));
+    }
+
+    // Messages left by the previous run are older than any new one, so new messages are put to memory
+    // only after them. Incomplete record at the end is left by a crash and is cut off.
+    async fn restore_spilled(&self, path: &str) {
+        let _spill_lock = self.spill_lock.lock().await;
+
+        let position = read_spill_position(path).await;
+        let mut messages_amount = 0;
+
+        if let Ok(mut reader) = MessagesRecordsReader::open(path, position).await {
+            loop {
+                match reader.read_next().await {
+                    Ok(Some((_, messages))) => messages_amount += messages.len(),
+                    Ok(None) => {
+                        self.cut_incomplete_record(path, reader.get_position())
+                            .await;
+                        break;
+                    }
+                    // Corrupted file is dropped by publish_spilled
+                    Err(_) => break,
+                }
+            }
+        }
+
+        let mut state = self.state.lock().unwrap();
+        // Messages spilled before the file is counted are in the file as well
+        state.spilled_messages = messages_amount;
+        state.restoring_spill_file = false;
+        self.report_depth(&state);
+    }
+
+    async fn cut_incomplete_record(&self, path: &str, position: u64) {
+        let file_size = match tokio::fs::metadata(path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return,
+        };
+
+        if file_size <= position {
+            return;
+        }
+
+        self.write_error(format!(
+            "Incomplete record at position {} of spilled messages file {} is dropped",
+            position, path
+        ));
+
+        let result = match tokio::fs::OpenOptions::new().write(true).open(path).await {
+            Ok(file) => file.set_len(position).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            self.write_error(format!(
+                "Spilled messages file {} can not be truncated: {:?}",
+                path, err
+            ));
+        }
+    }
+
+    // Records are published one by one and the position after each published record is saved next to
+    // the spill file, so a crash can only publish a record again. File is removed once it is published fully.
+    async fn publish_spilled(&self, publishers: &MySbPublishers, path: &str) {
+        let position_path = get_position_path(path);
+        let position = read_spill_position(path).await;
+
+        let mut reader = match MessagesRecordsReader::open(path, position).await {
+            Ok(reader) => reader,
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    self.write_error(format!(
+                        "Spilled messages can not be read from {}: {:?}",
+                        path, err
+                    ));
+                }
+
+                // Unreadable file is left as is, otherwise the publishing loop would spin on it
+                let _spill_lock = self.spill_lock.lock().await;
+                self.spilled_published(usize::MAX);
+                return;
+            }
+        };
+
+        loop {
+            let messages = match reader.read_next().await {
+                Ok(Some((_, messages))) => messages,
+                Ok(None) => {
+                    // Messages are spilled under the lock, so the file can not grow while it is held.
+                    // Record appended before the lock is taken is read here, anything else is a broken tail.
+                    let _spill_lock = self.spill_lock.lock().await;
+
+                    match reader.read_next().await {
+                        Ok(Some((_, messages))) => messages,
+                        Ok(None) => {
+                            let file_size = match tokio::fs::metadata(path).await {
+                                Ok(metadata) => metadata.len(),
+                                Err(_) => 0,
+                            };
+
+                            if file_size > reader.get_position() {
+                                self.write_error(format!(
+                                    "Spilled messages file {} has an incomplete record at position {}",
+                                    path,
+                                    reader.get_position()
+                                ));
+                            }
+
+                            self.drop_spill_file(path, position_path.as_str()).await;
+                            return;
+                        }
+                        Err(err) => {
+                            self.write_corrupted(path, reader.get_position(), err);
+                            self.drop_spill_file(path, position_path.as_str()).await;
+                            return;
+                        }
+                    }
+                }
+                Err(err) => {
+                    self.write_corrupted(path, reader.get_position(), err);
+
+                    let _spill_lock = self.spill_lock.lock().await;
+                    self.drop_spill_file(path, position_path.as_str()).await;
+                    return;
+                }
+            };
+
+            self.publish(publishers, messages.as_slice()).await;
+            self.spilled_published(messages.len());
+
+            let position = reader.get_position().to_string();
+            if let Err(err) = tokio::fs::write(position_path.as_str(), position).await {
+                self.write_error(format!(
+                    "Position of spilled messages can not be saved to {}: {:?}",
+                    position_path, err
+                ));
+            }
+        }
+    }
+
+    fn write_corrupted(&self, path: &str, position: u64, err: std::io::Error) {
+        self.write_error(format!(
+            "Spilled messages file {} is corrupted after position {}: {:?}",
+            path, position, err
+        ));
+    }
+
+    // Has to be called under the spill lock
+    async fn drop_spill_file(&self, path: &str, position_path: &str) {
+        self.remove_spill_file(path, position_path).await;
+        self.spilled_published(usize::MAX);
+    }
+
+    async fn remove_spill_file(&self, path: &str, position_path: &str) {
+        if let Err(err) = tokio::fs::remove_file(path).await {
+            self.write_error(format!(
+                "Spilled messages file {} can not be removed: {:?}",
+                path, err
+            ));
+        }
+
+        let _ = tokio::fs::remove_file(position_path).await;
+    }
+
+    async fn publish(&self, publishers: &MySbPublishers, messages: &[MessageToPublish]) {
+        // Retries keep the batch until the connection is back
+        let result = publishers
+            .publish_messages(self.topic_id.as_str(), messages, true)
+            .await;
+
+        if let Err(err) = result {
+            self.write_error(format!(
+                "{} queued messages can not be published: {:?}",
+                messages.len(),
+                err
+            ));
+        }
+    }
+
+    async fn run(self: Arc<Self>, publishers: Arc<MySbPublishers>) {
+        // Messages spilled by the previous run of the application are published first
+        let mut check_spill_file = match self.get_spill_path() {
+            Some(path) => {
+                self.restore_spilled(path).await;
+                true
+            }
+            None => false,
+        };
+
+        loop {
+            let new_messages = self.new_messages.notified();
+
+            if check_spill_file {
+                check_spill_file = false;
+
+                if let Some(path) = self.get_spill_path() {
+                    self.publish_spilled(&publishers, path).await;
+                }
+            }
+
+            let batch = self.take_batch();
+
+            if !batch.is_empty() {
+                self.publish(&publishers, batch.as_slice()).await;
+                self.release_batch(batch.as_slice());
+                continue;
+            }
+
+            // Spilled messages are newer than any message which was in memory
+            if self.has_spilled() {
+                check_spill_file = true;
+                continue;
+            }
+
+            if self.closed.load(Ordering::SeqCst) {
+                return;
+            }
+
+            new_messages.await;
+        }
+    }
+}
+
+fn get_position_path(path: &str) -> String {
+    format!("{}.position", path)
+}
+
+async fn read_spill_position(path: &str) -> u64 {
+    match tokio::fs::read_to_string(get_position_path(path)).await {
+        Ok(position) => position.trim().parse().unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+pub struct BoundedPublishQueue<TModel: MySbMessageSerializer> {
+    inner: Arc<BoundedPublishQueueInner>,
+    itm: PhantomData<TModel>,
+}
+
+impl<TModel: MySbMessageSerializer> BoundedPublishQueue<TModel> {
+    pub fn new(
+        topic_id: String,
+        settings: BoundedPublishQueueSettings,
+        publishers: Arc<MySbPublishers>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+    ) -> Self {
+        let restoring_spill_file = matches!(
+            settings.overflow_policy,
+            QueueOverflowPolicy::SpillToDisk(_)
+        );
+
+        let inner = Arc::new(BoundedPublishQueueInner {
+            topic_id,
+            settings,
+            state: Mutex::new(QueueState {
+                queued: VecDeque::new(),
+                publishing_messages: 0,
+                bytes: 0,
+                spilled_messages: 0,
+                restoring_spill_file,
+                dropped_messages: 0,
+            }),
+            spill_lock: tokio::sync::Mutex::new(()),
+            new_messages: Notify::new(),
+            space_released: Notify::new(),
+            closed: AtomicBool::new(false),
+            logger,
+        });
+
+        tokio::spawn(inner.clone().run(publishers));
+
+        Self {
+            inner,
+            itm: PhantomData,
+        }
+    }
+
+    pub async fn publish(&self, message: &TModel) -> Result<(), PublishError> {
+        let (content, headers) = message
+            .serialize(None)
+            .map_err(PublishError::SerializationError)?;
+
+        self.inner
+            .enqueue(MessageToPublish { headers, content })
+            .await
+    }
+
+    pub async fn publish_messages(&self, messages: &[TModel]) -> Result<(), PublishError> {
+        for message in messages {
+            self.publish(message).await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_queue_depth(&self) -> PublishQueueDepth {
+        let state = self.inner.state.lock().unwrap();
+        state.get_depth()
+    }
+}
+
+impl<TModel: MySbMessageSerializer> Drop for BoundedPublishQueue<TModel> {
+    fn drop(&mut self) {
+        // Publishing task stops once the queue is flushed
+        self.inner.closed.store(true, Ordering::SeqCst);
+        self.inner.new_messages.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::{atomic::AtomicBool, Arc, Mutex},
+        time::Duration,
+    };
+
+    use my_service_bus_abstractions::publisher::MessageToPublish;
+    use rust_extensions::Logger;
+    use tokio::sync::Notify;
+
+    use super::{
+        BoundedPublishQueueInner, BoundedPublishQueueSettings, QueueOverflowPolicy, QueueState,
+    };
+    use crate::publishers::{serialize_messages_record, MySbPublishers};
+
+    struct TestLogger;
+
+    impl Logger for TestLogger {
+        fn write_info(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_warning(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+        fn write_fatal_error(&self, _: String, _: String, _: Option<HashMap<String, String>>) {}
+    }
+
+    fn get_spill_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn create_inner(spill_path: &str) -> Arc<BoundedPublishQueueInner> {
+        Arc::new(BoundedPublishQueueInner {
+            topic_id: "test-topic".to_string(),
+            settings: BoundedPublishQueueSettings {
+                max_messages: 10,
+                max_bytes: 1024,
+                overflow_policy: QueueOverflowPolicy::SpillToDisk(spill_path.to_string()),
+            },
+            state: Mutex::new(QueueState {
+                queued: VecDeque::new(),
+                publishing_messages: 0,
+                bytes: 0,
+                spilled_messages: 0,
+                restoring_spill_file: true,
+                dropped_messages: 0,
+            }),
+            spill_lock: tokio::sync::Mutex::new(()),
+            new_messages: Notify::new(),
+            space_released: Notify::new(),
+            closed: AtomicBool::new(false),
+            logger: Arc::new(TestLogger),
+        })
+    }
+
+    fn create_message() -> MessageToPublish {
+        MessageToPublish {
+            headers: None,
+            content: vec![1, 2, 3],
+        }
+    }
+
+    fn create_truncated_record() -> Vec<u8> {
+        let record = serialize_messages_record("test-topic", &[create_message()]);
+        record[..record.len() - 1].to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_record_does_not_block_publishing_of_spilled() {
+        let path = get_spill_path("bounded-queue-incomplete-record");
+        tokio::fs::write(&path, create_truncated_record())
+            .await
+            .unwrap();
+
+        let inner = create_inner(&path);
+        inner.state.lock().unwrap().spilled_messages = 1;
+        let publishers = MySbPublishers::new("test-app".to_string());
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            inner.publish_spilled(&publishers, &path),
+        )
+        .await
+        .unwrap();
+
+        assert!(tokio::fs::metadata(&path).await.is_err());
+        assert_eq!(0, inner.state.lock().unwrap().spilled_messages);
+    }
+
+    #[tokio::test]
+    async fn test_run_moves_past_truncated_spill_file() {
+        let path = get_spill_path("bounded-queue-truncated-file");
+        tokio::fs::write(&path, create_truncated_record())
+            .await
+            .unwrap();
+
+        let inner = create_inner(&path);
+        let publishers = Arc::new(MySbPublishers::new("test-app".to_string()));
+        tokio::spawn(inner.clone().run(publishers));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tokio::fs::metadata(&path).await.is_ok() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        inner.enqueue(create_message()).await.unwrap();
+
+        assert_eq!(0, inner.state.lock().unwrap().spilled_messages);
+        assert!(tokio::fs::metadata(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_messages_are_spilled_after_spill_file_of_previous_run() {
+        let path = get_spill_path("bounded-queue-previous-run");
+        let record = serialize_messages_record("test-topic", &[create_message(), create_message()]);
+        tokio::fs::write(&path, record).await.unwrap();
+
+        let inner = create_inner(&path);
+        inner.restore_spilled(&path).await;
+        inner.enqueue(create_message()).await.unwrap();
+
+        let state = inner.state.lock().unwrap();
+        let spilled_messages = state.spilled_messages;
+        let queued = state.queued.len();
+        drop(state);
+
+        let records = crate::publishers::read_messages_records(&path)
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(3, spilled_messages);
+        assert_eq!(0, queued);
+        assert_eq!(2, records.len());
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct CreateTopicOptions {
+    pub timeout: Duration,
+    pub wait_for_connection: bool,
+}
+
+impl Default for CreateTopicOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            wait_for_connection: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CreateTopicError {
+    NoConnection,
+    Disconnected,
+    Rejected(String),
+    Timeout,
+}
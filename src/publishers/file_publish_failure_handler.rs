@@ -0,0 +1,60 @@
+use std::{collections::HashMap, sync::Arc};
+
+use my_service_bus_abstractions::{publisher::MessageToPublish, PublishError};
+use rust_extensions::Logger;
+use tokio::sync::Mutex;
+
+use super::{
+    append_messages_record, read_messages_records, serialize_messages_record, PublishFailureHandler,
+};
+
+// Appends failed batches to a file, which can be read back with `read_all` and published again
+pub struct FilePublishFailureHandler {
+    path: String,
+    write_lock: Mutex<()>,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+impl FilePublishFailureHandler {
+    pub fn new(path: impl Into<String>, logger: Arc<dyn Logger + Send + Sync + 'static>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: Mutex::new(()),
+            logger,
+        }
+    }
+
+    pub async fn read_all(path: &str) -> std::io::Result<Vec<(String, Vec<MessageToPublish>)>> {
+        read_messages_records(path).await
+    }
+}
+
+#[async_trait::async_trait]
+impl PublishFailureHandler for FilePublishFailureHandler {
+    async fn on_publish_failed(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        error: &PublishError,
+    ) {
+        let record = serialize_messages_record(topic_id, messages);
+
+        let _write_lock = self.write_lock.lock().await;
+
+        if let Err(err) = append_messages_record(self.path.as_str(), record.as_slice()).await {
+            let mut ctx = HashMap::new();
+            ctx.insert("TopicId".to_string(), topic_id.to_string());
+            ctx.insert("MessagesAmount".to_string(), messages.len().to_string());
+            ctx.insert("Error".to_string(), format!("{:?}", error));
+
+            self.logger.write_error(
+                "FilePublishFailureHandler".to_string(),
+                format!(
+                    "Failed messages can not be written to {}: {:?}",
+                    self.path, err
+                ),
+                Some(ctx),
+            );
+        }
+    }
+}
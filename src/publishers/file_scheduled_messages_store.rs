@@ -0,0 +1,153 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{TimeZone, Utc};
+use rust_extensions::Logger;
+
+use super::{
+    serialize_messages_record, MessagesRecordsReader, ScheduledMessage, ScheduledMessagesStore,
+};
+
+// Every scheduled message is kept in its own file named by message id.
+// File: publish_at as i64 LE unix milliseconds followed by a messages record with the single message.
+pub struct FileScheduledMessagesStore {
+    dir: String,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+impl FileScheduledMessagesStore {
+    pub fn new(dir: impl Into<String>, logger: Arc<dyn Logger + Send + Sync + 'static>) -> Self {
+        Self {
+            dir: dir.into(),
+            logger,
+        }
+    }
+
+    fn get_path(&self, id: &str) -> String {
+        format!("{}/{}", self.dir, id)
+    }
+
+    fn write_error(&self, message: String) {
+        let mut ctx = HashMap::new();
+        ctx.insert("Dir".to_string(), self.dir.clone());
+
+        self.logger
+            .write_error("FileScheduledMessagesStore".to_string(), message, Some(ctx));
+    }
+
+    async fn load(&self, id: String, path: &str) -> std::io::Result<Option<ScheduledMessage>> {
+        let content = tokio::fs::read(path).await?;
+
+        if content.len() < 8 {
+            return Ok(None);
+        }
+
+        let mut millis = [0u8; 8];
+        millis.copy_from_slice(&content[..8]);
+
+        let publish_at = match Utc
+            .timestamp_millis_opt(i64::from_le_bytes(millis))
+            .single()
+        {
+            Some(publish_at) => publish_at,
+            None => return Ok(None),
+        };
+
+        let mut reader = MessagesRecordsReader::open(path, 8).await?;
+
+        let (topic_id, mut messages) = match reader.read_next().await? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        if messages.len() != 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(ScheduledMessage {
+            id,
+            topic_id,
+            publish_at,
+            message: messages.remove(0),
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl ScheduledMessagesStore for FileScheduledMessagesStore {
+    async fn save(&self, message: &ScheduledMessage) {
+        let mut content = message.publish_at.timestamp_millis().to_le_bytes().to_vec();
+        content.extend_from_slice(
+            serialize_messages_record(
+                message.topic_id.as_str(),
+                std::slice::from_ref(&message.message),
+            )
+            .as_slice(),
+        );
+
+        let result = match tokio::fs::create_dir_all(self.dir.as_str()).await {
+            Ok(_) => tokio::fs::write(self.get_path(message.id.as_str()), content).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            self.write_error(format!(
+                "Scheduled message {} can not be saved: {:?}",
+                message.id, err
+            ));
+        }
+    }
+
+    async fn remove(&self, id: &str) {
+        if let Err(err) = tokio::fs::remove_file(self.get_path(id)).await {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                self.write_error(format!(
+                    "Scheduled message {} can not be removed: {:?}",
+                    id, err
+                ));
+            }
+        }
+    }
+
+    async fn load_all(&self) -> Vec<ScheduledMessage> {
+        let mut result = Vec::new();
+
+        let mut entries = match tokio::fs::read_dir(self.dir.as_str()).await {
+            Ok(entries) => entries,
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    self.write_error(format!("Scheduled messages can not be listed: {:?}", err));
+                }
+                return result;
+            }
+        };
+
+        loop {
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(err) => {
+                    self.write_error(format!("Scheduled messages can not be listed: {:?}", err));
+                    break;
+                }
+            };
+
+            let id = entry.file_name().to_string_lossy().to_string();
+            let path = self.get_path(id.as_str());
+
+            match self.load(id.clone(), path.as_str()).await {
+                Ok(Some(scheduled)) => result.push(scheduled),
+                Ok(None) => {
+                    self.write_error(format!("Scheduled message file {} is corrupted", path));
+                }
+                Err(err) => {
+                    self.write_error(format!(
+                        "Scheduled message {} can not be loaded: {:?}",
+                        id, err
+                    ));
+                }
+            }
+        }
+
+        result
+    }
+}
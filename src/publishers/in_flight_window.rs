@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use my_service_bus_abstractions::PublishError;
+
+// Prefix of the PublishError::Other message returned when the window is full in FailFast mode
+pub const IN_FLIGHT_WINDOW_FULL: &str = "In-flight publish window is full";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InFlightWindowFullBehavior {
+    Wait,
+    // Publish fails with PublishError::Other starting with IN_FLIGHT_WINDOW_FULL, see is_in_flight_window_full
+    FailFast,
+}
+
+pub fn is_in_flight_window_full(err: &PublishError) -> bool {
+    match err {
+        PublishError::Other(message) => message.starts_with(IN_FLIGHT_WINDOW_FULL),
+        _ => false,
+    }
+}
+
+pub fn in_flight_window_full_error(topic_id: &str) -> PublishError {
+    PublishError::Other(format!("{}. Topic: {}", IN_FLIGHT_WINDOW_FULL, topic_id))
+}
+
+#[derive(Debug, Clone)]
+pub struct InFlightWindowSettings {
+    pub max_requests: usize,
+    pub max_bytes: usize,
+    pub when_full: InFlightWindowFullBehavior,
+}
+
+impl InFlightWindowSettings {
+    pub fn has_room(&self, requests: usize, bytes: usize, payload_size: usize) -> bool {
+        has_room(
+            requests,
+            bytes,
+            payload_size,
+            self.max_requests,
+            self.max_bytes,
+        )
+    }
+}
+
+// Empty window always accepts an item, otherwise an item bigger than max_bytes would never go through
+pub fn has_room(
+    items: usize,
+    bytes: usize,
+    item_size: usize,
+    max_items: usize,
+    max_bytes: usize,
+) -> bool {
+    if items == 0 {
+        return true;
+    }
+
+    items < max_items && bytes + item_size <= max_bytes
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InFlightWindowStats {
+    pub waited: u64,
+    pub rejected: u64,
+}
+
+pub struct InFlightWindowMetrics {
+    waited: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl InFlightWindowMetrics {
+    pub fn new() -> Self {
+        Self {
+            waited: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc_waited(&self) {
+        self.waited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get_stats(&self) -> InFlightWindowStats {
+        InFlightWindowStats {
+            waited: self.waited.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
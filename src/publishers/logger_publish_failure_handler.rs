@@ -0,0 +1,51 @@
+use std::{collections::HashMap, sync::Arc};
+
+use my_service_bus_abstractions::{publisher::MessageToPublish, PublishError};
+use rust_extensions::Logger;
+
+use super::{PublishFailureHandler, MESSAGE_ID_HEADER};
+
+pub struct LoggerPublishFailureHandler {
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+impl LoggerPublishFailureHandler {
+    pub fn new(logger: Arc<dyn Logger + Send + Sync + 'static>) -> Self {
+        Self { logger }
+    }
+}
+
+#[async_trait::async_trait]
+impl PublishFailureHandler for LoggerPublishFailureHandler {
+    async fn on_publish_failed(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        error: &PublishError,
+    ) {
+        let mut ctx = HashMap::new();
+        ctx.insert("TopicId".to_string(), topic_id.to_string());
+        ctx.insert("MessagesAmount".to_string(), messages.len().to_string());
+        ctx.insert("Error".to_string(), format!("{:?}", error));
+
+        let message_ids: Vec<&str> = messages
+            .iter()
+            .filter_map(|message| message.headers.as_ref()?.get(MESSAGE_ID_HEADER))
+            .map(|id| id.as_str())
+            .collect();
+
+        if !message_ids.is_empty() {
+            ctx.insert("MessageIds".to_string(), message_ids.join(","));
+        }
+
+        self.logger.write_error(
+            "PublishFailureHandler".to_string(),
+            format!(
+                "{} messages to topic {} are not published",
+                messages.len(),
+                topic_id
+            ),
+            Some(ctx),
+        );
+    }
+}
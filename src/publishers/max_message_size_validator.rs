@@ -0,0 +1,32 @@
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+use super::{get_message_size, MessageValidator};
+
+pub struct MaxMessageSizeValidator {
+    max_size: usize,
+}
+
+impl MaxMessageSizeValidator {
+    pub fn new(max_size: usize) -> Self {
+        Self { max_size }
+    }
+}
+
+impl MessageValidator for MaxMessageSizeValidator {
+    fn get_name(&self) -> &str {
+        "MaxMessageSize"
+    }
+
+    fn validate(&self, _topic_id: &str, message: &MessageToPublish) -> Result<(), String> {
+        let size = get_message_size(message);
+
+        if size > self.max_size {
+            return Err(format!(
+                "Message size {} is bigger than {}",
+                size, self.max_size
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+pub const MESSAGE_ID_HEADER: &str = "my-sb-message-id";
+pub const PRODUCER_APP_HEADER: &str = "my-sb-producer-app";
+pub const PRODUCER_INSTANCE_HEADER: &str = "my-sb-producer-instance";
+pub const PUBLISHED_AT_HEADER: &str = "my-sb-published-at";
+
+pub struct MessageHeadersStamper {
+    app_name: String,
+    instance_id: String,
+}
+
+impl MessageHeadersStamper {
+    pub fn new(app_name: String) -> Self {
+        Self {
+            app_name,
+            instance_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    // Stamped once per publish call, so retries carry the same message id
+    pub fn stamp(&self, messages: &[MessageToPublish]) -> Vec<MessageToPublish> {
+        let published_at = chrono::Utc::now().to_rfc3339();
+
+        messages
+            .iter()
+            .map(|message| {
+                let mut headers = message.headers.clone().unwrap_or_default();
+
+                if !headers.contains_key(MESSAGE_ID_HEADER) {
+                    headers.insert(
+                        MESSAGE_ID_HEADER.to_string(),
+                        uuid::Uuid::new_v4().to_string(),
+                    );
+                }
+
+                headers.insert(PRODUCER_APP_HEADER.to_string(), self.app_name.clone());
+                headers.insert(
+                    PRODUCER_INSTANCE_HEADER.to_string(),
+                    self.instance_id.clone(),
+                );
+                headers.insert(PUBLISHED_AT_HEADER.to_string(), published_at.clone());
+
+                MessageToPublish {
+                    headers: Some(headers),
+                    content: message.content.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use my_service_bus_abstractions::publisher::MessageToPublish;
+
+    use super::{
+        MessageHeadersStamper, MESSAGE_ID_HEADER, PRODUCER_APP_HEADER, PRODUCER_INSTANCE_HEADER,
+        PUBLISHED_AT_HEADER,
+    };
+
+    fn get_header<'s>(message: &'s MessageToPublish, key: &str) -> Option<&'s String> {
+        message.headers.as_ref()?.get(key)
+    }
+
+    #[test]
+    fn test_existing_message_id_is_kept() {
+        let stamper = MessageHeadersStamper::new("test-app".to_string());
+
+        let mut headers = HashMap::new();
+        headers.insert(MESSAGE_ID_HEADER.to_string(), "existing-id".to_string());
+
+        let stamped = stamper.stamp(&[MessageToPublish {
+            headers: Some(headers),
+            content: vec![1],
+        }]);
+
+        assert_eq!(
+            "existing-id",
+            get_header(&stamped[0], MESSAGE_ID_HEADER).unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn test_missing_message_id_is_generated() {
+        let stamper = MessageHeadersStamper::new("test-app".to_string());
+
+        let messages = vec![
+            MessageToPublish {
+                headers: None,
+                content: vec![1],
+            },
+            MessageToPublish {
+                headers: None,
+                content: vec![2],
+            },
+        ];
+
+        let stamped = stamper.stamp(&messages);
+
+        let first_id = get_header(&stamped[0], MESSAGE_ID_HEADER).unwrap();
+        let second_id = get_header(&stamped[1], MESSAGE_ID_HEADER).unwrap();
+        assert_ne!(first_id, second_id);
+
+        assert_eq!(
+            "test-app",
+            get_header(&stamped[0], PRODUCER_APP_HEADER)
+                .unwrap()
+                .as_str()
+        );
+        assert!(get_header(&stamped[0], PRODUCER_INSTANCE_HEADER).is_some());
+        assert!(get_header(&stamped[0], PUBLISHED_AT_HEADER).is_some());
+        assert_eq!(vec![1], stamped[0].content);
+    }
+}
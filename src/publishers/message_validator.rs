@@ -0,0 +1,8 @@
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+// Called with the serialized message before interceptors; Err rejects the whole publish call
+pub trait MessageValidator {
+    fn get_name(&self) -> &str;
+
+    fn validate(&self, topic_id: &str, message: &MessageToPublish) -> Result<(), String>;
+}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, io::SeekFrom};
+
+use my_service_bus_abstractions::publisher::MessageToPublish;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader},
+};
+
+// Record: topic_id, messages amount, then headers and content of each message.
+// Every string and byte array is prefixed with its u32 LE length.
+pub fn serialize_messages_record(topic_id: &str, messages: &[MessageToPublish]) -> Vec<u8> {
+    let mut result = Vec::new();
+
+    write_bytes(&mut result, topic_id.as_bytes());
+    result.extend_from_slice(&(messages.len() as u32).to_le_bytes());
+
+    for message in messages {
+        match message.headers.as_ref() {
+            Some(headers) => {
+                result.extend_from_slice(&(headers.len() as u32).to_le_bytes());
+                for (key, value) in headers {
+                    write_bytes(&mut result, key.as_bytes());
+                    write_bytes(&mut result, value.as_bytes());
+                }
+            }
+            None => result.extend_from_slice(&0u32.to_le_bytes()),
+        }
+
+        write_bytes(&mut result, message.content.as_slice());
+    }
+
+    result
+}
+
+// Partly written record is cut off, so records appended after a failed write stay readable
+pub async fn append_messages_record(path: &str, record: &[u8]) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+
+    let file_size = file.metadata().await?.len();
+
+    if let Err(err) = file.write_all(record).await {
+        let _ = file.set_len(file_size).await;
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+pub async fn read_messages_records(
+    path: &str,
+) -> std::io::Result<Vec<(String, Vec<MessageToPublish>)>> {
+    let mut reader = MessagesRecordsReader::open(path, 0).await?;
+
+    let mut result = Vec::new();
+
+    while let Some(record) = reader.read_next().await? {
+        result.push(record);
+    }
+
+    let file_size = tokio::fs::metadata(path).await?.len();
+
+    if reader.get_position() < file_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Messages file is truncated",
+        ));
+    }
+
+    Ok(result)
+}
+
+// Reads records one by one, so a big file is never loaded into memory as a whole
+pub struct MessagesRecordsReader {
+    reader: BufReader<File>,
+    position: u64,
+    record_size: u64,
+}
+
+impl MessagesRecordsReader {
+    pub async fn open(path: &str, position: u64) -> std::io::Result<Self> {
+        let mut file = File::open(path).await?;
+        file.seek(SeekFrom::Start(position)).await?;
+
+        Ok(Self {
+            reader: BufReader::new(file),
+            position,
+            record_size: 0,
+        })
+    }
+
+    // Position right after the last complete record which is read
+    pub fn get_position(&self) -> u64 {
+        self.position
+    }
+
+    // None if there is no complete record after the current position
+    pub async fn read_next(&mut self) -> std::io::Result<Option<(String, Vec<MessageToPublish>)>> {
+        self.record_size = 0;
+
+        match self.read_record().await {
+            Ok(record) => {
+                self.position += self.record_size;
+                Ok(Some(record))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                // Incomplete record is either being appended right now or left by a failed write,
+                // only the caller can tell which one it is
+                self.reader.seek(SeekFrom::Start(self.position)).await?;
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn read_record(&mut self) -> std::io::Result<(String, Vec<MessageToPublish>)> {
+        let topic_id = self.read_string().await?;
+        let messages_amount = self.read_u32().await?;
+
+        let mut messages = Vec::with_capacity(messages_amount as usize);
+
+        for _ in 0..messages_amount {
+            let headers_amount = self.read_u32().await?;
+
+            let headers = if headers_amount == 0 {
+                None
+            } else {
+                let mut headers = HashMap::new();
+                for _ in 0..headers_amount {
+                    let key = self.read_string().await?;
+                    let value = self.read_string().await?;
+                    headers.insert(key, value);
+                }
+                Some(headers)
+            };
+
+            let content = self.read_bytes().await?;
+
+            messages.push(MessageToPublish { headers, content });
+        }
+
+        Ok((topic_id, messages))
+    }
+
+    async fn read_u32(&mut self) -> std::io::Result<u32> {
+        let mut buffer = [0u8; 4];
+        self.reader.read_exact(&mut buffer).await?;
+        self.record_size += 4;
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    async fn read_bytes(&mut self) -> std::io::Result<Vec<u8>> {
+        let len = self.read_u32().await? as usize;
+        let mut result = vec![0u8; len];
+        self.reader.read_exact(&mut result).await?;
+        self.record_size += len as u64;
+        Ok(result)
+    }
+
+    async fn read_string(&mut self) -> std::io::Result<String> {
+        let bytes = self.read_bytes().await?;
+        String::from_utf8(bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+}
+
+fn write_bytes(dest: &mut Vec<u8>, value: &[u8]) {
+    dest.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    dest.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use my_service_bus_abstractions::publisher::MessageToPublish;
+
+    use super::{
+        append_messages_record, read_messages_records, serialize_messages_record,
+        MessagesRecordsReader,
+    };
+
+    fn get_temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn create_messages() -> Vec<MessageToPublish> {
+        let mut headers = HashMap::new();
+        headers.insert("key".to_string(), "value".to_string());
+
+        vec![
+            MessageToPublish {
+                headers: Some(headers),
+                content: vec![1, 2, 3],
+            },
+            MessageToPublish {
+                headers: None,
+                content: vec![],
+            },
+        ]
+    }
+
+    fn assert_messages(expected: &[MessageToPublish], actual: &[MessageToPublish]) {
+        assert_eq!(expected.len(), actual.len());
+
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert_eq!(expected.content, actual.content);
+
+            let expected_headers = expected.headers.clone().unwrap_or_default();
+            let actual_headers = actual.headers.clone().unwrap_or_default();
+            assert_eq!(expected_headers, actual_headers);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_round_trip() {
+        let path = get_temp_path("messages-file-round-trip");
+        let messages = create_messages();
+
+        append_messages_record(&path, &serialize_messages_record("topic-a", &messages))
+            .await
+            .unwrap();
+        append_messages_record(&path, &serialize_messages_record("topic-b", &messages[1..]))
+            .await
+            .unwrap();
+
+        let records = read_messages_records(&path).await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(2, records.len());
+        assert_eq!("topic-a", records[0].0);
+        assert_messages(&messages, &records[0].1);
+        assert_eq!("topic-b", records[1].0);
+        assert_messages(&messages[1..], &records[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_reader_resumes_from_position() {
+        let path = get_temp_path("messages-file-position");
+        let messages = create_messages();
+
+        append_messages_record(&path, &serialize_messages_record("topic-a", &messages))
+            .await
+            .unwrap();
+        append_messages_record(&path, &serialize_messages_record("topic-b", &messages))
+            .await
+            .unwrap();
+
+        let mut reader = MessagesRecordsReader::open(&path, 0).await.unwrap();
+        reader.read_next().await.unwrap().unwrap();
+        let position = reader.get_position();
+
+        let mut reader = MessagesRecordsReader::open(&path, position).await.unwrap();
+        let (topic_id, _) = reader.read_next().await.unwrap().unwrap();
+        let next = reader.read_next().await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!("topic-b", topic_id);
+        assert!(next.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_record_is_reported() {
+        let path = get_temp_path("messages-file-truncated");
+        let record = serialize_messages_record("topic-a", &create_messages());
+
+        append_messages_record(&path, &record[..record.len() - 1])
+            .await
+            .unwrap();
+
+        let result = read_messages_records(&path).await;
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert!(result.is_err());
+    }
+}
@@ -1,7 +1,57 @@
+mod bounded_publish_queue;
+mod create_topic;
+mod file_publish_failure_handler;
+mod file_scheduled_messages_store;
+mod in_flight_window;
+mod logger_publish_failure_handler;
+mod max_message_size_validator;
+mod message_headers_stamper;
+mod message_validator;
+mod messages_file;
 mod my_sb_publisher;
 mod my_sb_publisher_data;
+mod my_sb_raw_message;
+mod ordered_publish_queues;
+mod pending_publish_requests;
+mod persist_immediately;
+mod publish_cancellation;
+mod publish_chunks;
+mod publish_failure_handler;
+mod publish_interceptor;
+mod publish_metrics;
+mod publish_packet;
 mod publish_process_by_connection;
+mod publish_scheduler;
+mod publisher_settings;
+mod rate_limiter;
+mod required_headers_validator;
+mod scheduled_messages_store;
 
+pub use bounded_publish_queue::*;
+pub use create_topic::*;
+pub use file_publish_failure_handler::FilePublishFailureHandler;
+pub use file_scheduled_messages_store::FileScheduledMessagesStore;
+pub use in_flight_window::*;
+pub use logger_publish_failure_handler::LoggerPublishFailureHandler;
+pub use max_message_size_validator::MaxMessageSizeValidator;
+pub use message_headers_stamper::*;
+pub use message_validator::MessageValidator;
+pub use messages_file::*;
 pub use my_sb_publisher::MySbPublishers;
 pub use my_sb_publisher_data::MySbPublisherData;
+pub use my_sb_raw_message::MySbRawMessage;
+pub use ordered_publish_queues::OrderedPublishQueues;
+pub use pending_publish_requests::*;
+pub use persist_immediately::*;
+pub use publish_cancellation::*;
+pub use publish_chunks::*;
+pub use publish_failure_handler::PublishFailureHandler;
+pub use publish_interceptor::PublishInterceptor;
+pub use publish_metrics::*;
+pub use publish_packet::PublishPacket;
 pub use publish_process_by_connection::PublishProcessByConnection;
+pub use publish_scheduler::PublishScheduler;
+pub use publisher_settings::{PublisherSettings, PublisherSettingsError};
+pub use rate_limiter::*;
+pub use required_headers_validator::RequiredHeadersValidator;
+pub use scheduled_messages_store::*;
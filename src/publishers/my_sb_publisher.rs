@@ -1,41 +1,182 @@
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Instant,
+};
 
 use my_service_bus_abstractions::{
     publisher::MessageToPublish, MyServiceBusPublisherClient, PublishError,
 };
 use my_service_bus_tcp_shared::{MySbTcpSerializer, TcpContract};
 use my_tcp_sockets::tcp_connection::SocketConnection;
-use tokio::sync::Mutex;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 
 use crate::new_connection_handler::PROTOCOL_VERSION;
 
-use super::{MySbPublisherData, PublishProcessByConnection};
+use super::{
+    get_message_size, has_persist_immediately_header, in_flight_window_full_error,
+    rate_limit_exceeded_error, take_persist_immediately_header, CancellablePublishError,
+    ChunkRetryMode, CreateTopicError, CreateTopicOptions, InFlightWindowFullBehavior,
+    InFlightWindowMetrics, InFlightWindowSettings, InFlightWindowStats, MessageHeadersStamper,
+    MessageValidator, MySbPublisherData, OrderedPublishQueues, PendingPublishGuard,
+    PublishChunkError, PublishChunkSettings, PublishFailureHandler, PublishInterceptor,
+    PublishMetrics, PublishPacket, PublishProcessByConnection, PublishRateLimit, PublishRequest,
+    PublisherSettings, PublisherSettingsError, RateLimitExceededBehavior, RateLimiters,
+    TopicPublishMetricsSnapshot,
+};
 
 pub struct MySbPublishers {
-    data: Mutex<MySbPublisherData>,
+    request_id: AtomicI64,
+    connection: RwLock<Option<Arc<PublishProcessByConnection>>>,
+    data: RwLock<MySbPublisherData>,
+    in_flight_released: Notify,
+    in_flight_metrics: InFlightWindowMetrics,
+    headers_stamper: MessageHeadersStamper,
+    metrics: PublishMetrics,
+    rate_limiters: RateLimiters,
+    to_resend: Mutex<BTreeMap<i64, PublishRequest>>,
+    ordered_queues: OrderedPublishQueues,
 }
 
 impl MySbPublishers {
-    pub fn new() -> Self {
-        let data = MySbPublisherData::new();
+    pub fn new(app_name: String) -> Self {
         Self {
-            data: Mutex::new(data),
+            request_id: AtomicI64::new(0),
+            connection: RwLock::new(None),
+            data: RwLock::new(MySbPublisherData::new()),
+            in_flight_released: Notify::new(),
+            in_flight_metrics: InFlightWindowMetrics::new(),
+            headers_stamper: MessageHeadersStamper::new(app_name),
+            metrics: PublishMetrics::new(),
+            rate_limiters: RateLimiters::new(),
+            to_resend: Mutex::new(BTreeMap::new()),
+            ordered_queues: OrderedPublishQueues::new(),
         }
     }
 
+    fn get_next_request_id(&self) -> i64 {
+        self.request_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn get_connection(&self) -> Option<Arc<PublishProcessByConnection>> {
+        let read_access = self.connection.read().unwrap();
+        read_access.clone()
+    }
+
     pub async fn set_confirmed(&self, request_id: i64) {
-        let mut write_access = self.data.lock().await;
-        write_access.confirm(request_id).await;
+        if let Some(connection) = self.get_connection() {
+            if connection.confirm(request_id) {
+                self.in_flight_released.notify_waiters();
+            }
+        }
+    }
+
+    pub async fn set_in_flight_window(&self, settings: InFlightWindowSettings) {
+        {
+            let mut write_access = self.data.write().unwrap();
+            write_access.in_flight_window = Some(settings);
+        }
+
+        self.in_flight_released.notify_waiters();
+    }
+
+    fn get_in_flight_window(&self) -> Option<InFlightWindowSettings> {
+        let read_access = self.data.read().unwrap();
+        read_access.in_flight_window.clone()
+    }
+
+    pub fn get_in_flight_window_stats(&self) -> InFlightWindowStats {
+        self.in_flight_metrics.get_stats()
+    }
+
+    pub fn get_publish_metrics(&self) -> Vec<TopicPublishMetricsSnapshot> {
+        self.metrics.get_snapshot()
+    }
+
+    pub async fn set_publisher_settings(
+        &self,
+        topic_id: String,
+        settings: PublisherSettings,
+    ) -> Result<(), PublisherSettingsError> {
+        if let Some(rate_limit) = settings.rate_limit.as_ref() {
+            if rate_limit.has_zero_rate() {
+                return Err(PublisherSettingsError::ZeroRateLimit { topic_id });
+            }
+        }
+
+        self.rate_limiters.reset(topic_id.as_str());
+
+        let mut write_access = self.data.write().unwrap();
+        write_access.publisher_settings.insert(topic_id, settings);
+        Ok(())
+    }
+
+    async fn wait_for_rate_limit(
+        &self,
+        topic_id: &str,
+        rate_limit: &PublishRateLimit,
+        messages: &[MessageToPublish],
+    ) -> Result<(), PublishError> {
+        let limiter = self.rate_limiters.get(topic_id, rate_limit);
+
+        let bytes = messages.iter().map(get_message_size).sum();
+        let mut waited = false;
+
+        loop {
+            let wait = match limiter.try_acquire(messages.len(), bytes) {
+                Ok(_) => return Ok(()),
+                Err(wait) => wait,
+            };
+
+            match rate_limit.when_exceeded {
+                RateLimitExceededBehavior::Wait => {
+                    if !waited {
+                        waited = true;
+                        self.metrics.get(topic_id).throttled();
+                    }
+
+                    tokio::time::sleep(wait).await;
+                }
+                RateLimitExceededBehavior::Fail => {
+                    self.metrics.get(topic_id).rate_limited();
+                    return Err(rate_limit_exceeded_error(topic_id));
+                }
+            }
+        }
+    }
+
+    fn get_publisher_settings(&self, topic_id: &str) -> PublisherSettings {
+        let read_access = self.data.read().unwrap();
+        match read_access.publisher_settings.get(topic_id) {
+            Some(settings) => settings.clone(),
+            None => PublisherSettings::default(),
+        }
+    }
+
+    pub async fn set_resend_on_reconnect(&self, value: bool) {
+        let mut write_access = self.data.write().unwrap();
+        write_access.resend_on_reconnect = value;
+    }
+
+    fn get_resend_on_reconnect(&self) -> bool {
+        let read_access = self.data.read().unwrap();
+        read_access.resend_on_reconnect
+    }
+
+    fn take_to_resend(&self) -> BTreeMap<i64, PublishRequest> {
+        let mut write_access = self.to_resend.lock().unwrap();
+        std::mem::take(&mut *write_access)
     }
 
     pub async fn new_connection(
         &self,
         connection: Arc<SocketConnection<TcpContract, MySbTcpSerializer>>,
     ) {
-        {
-            let mut write_access = self.data.lock().await;
-            write_access.connection = Some(PublishProcessByConnection::new(connection.clone()));
-        }
+        let publish_process = Arc::new(PublishProcessByConnection::new(connection.clone()));
 
         for topic_id in self.get_topics_to_create().await {
             let packet = TcpContract::CreateTopicIfNotExists { topic_id };
@@ -44,37 +185,137 @@ impl MySbPublishers {
                 .send_bytes(packet.serialize(PROTOCOL_VERSION).as_slice())
                 .await;
         }
+
+        // Connection becomes visible to publishers only after unconfirmed packets are resent,
+        // so new publishes can not get ahead of them
+        for (request_id, request) in self.take_to_resend() {
+            publish_process.resend(request_id, request).await;
+        }
+
+        let mut write_access = self.connection.write().unwrap();
+        *write_access = Some(publish_process);
     }
 
     pub async fn disconnect(&self) {
-        let mut write_access = self.data.lock().await;
-        write_access.disconnect();
+        let connection = {
+            let mut write_access = self.connection.write().unwrap();
+            write_access.take()
+        };
+
+        if let Some(connection) = connection {
+            if self.get_resend_on_reconnect() {
+                let mut write_access = self.to_resend.lock().unwrap();
+                write_access.extend(connection.take_all());
+            } else {
+                connection.fail_all();
+            }
+        }
+
+        self.in_flight_released.notify_waiters();
+    }
+
+    // Request can be pending on the connection it was sent to, waiting for a resend or already resent
+    pub fn remove_pending_request(
+        &self,
+        connection: Option<&PublishProcessByConnection>,
+        request_id: i64,
+    ) {
+        if let Some(connection) = connection {
+            connection.remove(request_id);
+        }
+
+        if let Some(current) = self.get_connection() {
+            current.remove(request_id);
+        }
+
+        {
+            let mut write_access = self.to_resend.lock().unwrap();
+            write_access.remove(&request_id);
+        }
+
+        self.in_flight_released.notify_waiters();
     }
 
     pub async fn create_topic_if_not_exists(&self, topic_id: String) {
-        let mut write_access = self.data.lock().await;
+        let mut write_access = self.data.write().unwrap();
         write_access.topics_to_create.insert(topic_id, 0);
     }
 
     pub async fn get_topics_to_create(&self) -> Vec<String> {
         let mut result = Vec::new();
-        let write_access = self.data.lock().await;
+        let read_access = self.data.read().unwrap();
 
-        for topic_id in write_access.topics_to_create.keys() {
+        for topic_id in read_access.topics_to_create.keys() {
             result.push(topic_id.to_string());
         }
 
         result
     }
 
+    pub fn set_rejected(&self, reason: String) {
+        if let Some(connection) = self.get_connection() {
+            connection.set_rejected(reason);
+        }
+    }
+
+    // Broker does not answer CreateTopicIfNotExists, so an empty publish to the same topic is sent
+    // right after it. Packets are handled in order, so its PublishResponse acknowledges the topic.
+    pub async fn create_topic(
+        &self,
+        topic_id: &str,
+        options: &CreateTopicOptions,
+    ) -> Result<(), CreateTopicError> {
+        self.create_topic_if_not_exists(topic_id.to_string()).await;
+
+        if options.wait_for_connection {
+            self.wait_until_connection_is_restored().await;
+        }
+
+        let connection = match self.get_connection() {
+            Some(connection) => connection,
+            None => return Err(CreateTopicError::NoConnection),
+        };
+
+        let packet = TcpContract::CreateTopicIfNotExists {
+            topic_id: topic_id.to_string(),
+        };
+
+        connection
+            .socket
+            .send_bytes(packet.serialize(PROTOCOL_VERSION).as_slice())
+            .await;
+
+        let barrier = PublishPacket::compile(topic_id, self.get_next_request_id(), &[], false);
+        connection.try_acquire_in_flight(None, barrier.payload_size);
+        let awaiter = connection.publish(&barrier).await;
+
+        match tokio::time::timeout(options.timeout, awaiter.get_result()).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(_)) => match connection.get_reject_reason() {
+                Some(reason) => Err(CreateTopicError::Rejected(reason)),
+                None => Err(CreateTopicError::Disconnected),
+            },
+            // With resend on reconnect the barrier of a lost connection waits in to_resend instead of failing
+            Err(_) => {
+                let connected = match self.get_connection() {
+                    Some(current) => Arc::ptr_eq(&current, &connection),
+                    None => false,
+                };
+
+                self.remove_pending_request(Some(connection.as_ref()), barrier.request_id);
+
+                match connection.get_reject_reason() {
+                    Some(reason) => Err(CreateTopicError::Rejected(reason)),
+                    None if connected => Err(CreateTopicError::Timeout),
+                    None => Err(CreateTopicError::Disconnected),
+                }
+            }
+        }
+    }
+
     async fn wait_until_connection_is_restored(&self) {
         loop {
-            let has_connection = {
-                let read_access = self.data.lock().await;
-                read_access.connection.is_some()
-            };
-
-            if has_connection {
+            if self.get_connection().is_some() {
                 return;
             }
 
@@ -83,83 +324,468 @@ impl MySbPublishers {
             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         }
     }
-}
 
-#[async_trait::async_trait]
-impl MyServiceBusPublisherClient for MySbPublishers {
-    async fn publish_message(
+    async fn publish_packet(
         &self,
         topic_id: &str,
-        message: MessageToPublish,
-        do_retry: bool,
+        messages: &[MessageToPublish],
+        persist_immediately: bool,
+        to_send: &mut Option<PublishPacket>,
     ) -> Result<(), PublishError> {
-        return self.publish_messages(topic_id, &[message], do_retry).await;
+        let mut waited = false;
+
+        loop {
+            let in_flight_released = self.in_flight_released.notified();
+
+            let awaiter = {
+                let connection = match self.get_connection() {
+                    Some(connection) => connection,
+                    None => return Err(PublishError::NoConnectionToPublish),
+                };
+
+                if to_send.is_none() {
+                    let request_id = self.get_next_request_id();
+                    *to_send = Some(PublishPacket::compile(
+                        topic_id,
+                        request_id,
+                        messages,
+                        persist_immediately,
+                    ));
+                }
+
+                let packet = to_send.as_ref().unwrap();
+
+                let window = self.get_in_flight_window();
+
+                if connection.try_acquire_in_flight(window.as_ref(), packet.payload_size) {
+                    let started = Instant::now();
+                    let guard = PendingPublishGuard::new(self, &connection, packet.request_id);
+                    Some((connection.publish(packet).await, started, guard))
+                } else {
+                    match window.unwrap().when_full {
+                        InFlightWindowFullBehavior::Wait => {
+                            if !waited {
+                                waited = true;
+                                self.in_flight_metrics.inc_waited();
+                            }
+
+                            None
+                        }
+                        InFlightWindowFullBehavior::FailFast => {
+                            self.in_flight_metrics.inc_rejected();
+                            return Err(in_flight_window_full_error(topic_id));
+                        }
+                    }
+                }
+            };
+
+            // Connection is released before awaiting, so a disconnect can fail the request
+            match awaiter {
+                Some((awaiter, started, mut guard)) => {
+                    let result = awaiter.get_result().await;
+                    guard.complete();
+
+                    if result.is_ok() {
+                        self.metrics
+                            .get(topic_id)
+                            .published(messages.len(), started.elapsed());
+                    }
+
+                    return result;
+                }
+                None => in_flight_released.await,
+            }
+        }
     }
 
-    async fn publish_messages(
+    pub async fn set_publish_chunk_settings(&self, settings: PublishChunkSettings) {
+        let mut write_access = self.data.write().unwrap();
+        write_access.chunk_settings = Some(settings);
+    }
+
+    fn get_publish_chunk_settings(&self) -> Option<PublishChunkSettings> {
+        let read_access = self.data.read().unwrap();
+        read_access.chunk_settings.clone()
+    }
+
+    pub async fn add_interceptor(
+        &self,
+        interceptor: Arc<dyn PublishInterceptor + Send + Sync + 'static>,
+    ) {
+        let mut write_access = self.data.write().unwrap();
+        write_access.interceptors.push(interceptor);
+    }
+
+    fn get_interceptors(&self) -> Vec<Arc<dyn PublishInterceptor + Send + Sync + 'static>> {
+        let read_access = self.data.read().unwrap();
+        read_access.interceptors.clone()
+    }
+
+    pub async fn add_validator(
+        &self,
+        topic_id: String,
+        validator: Arc<dyn MessageValidator + Send + Sync + 'static>,
+    ) {
+        let mut write_access = self.data.write().unwrap();
+        write_access
+            .validators
+            .entry(topic_id)
+            .or_default()
+            .push(validator);
+    }
+
+    fn validate(&self, topic_id: &str, messages: &[MessageToPublish]) -> Result<(), PublishError> {
+        let read_access = self.data.read().unwrap();
+
+        let validators = match read_access.validators.get(topic_id) {
+            Some(validators) => validators,
+            None => return Ok(()),
+        };
+
+        for message in messages {
+            for validator in validators {
+                if let Err(reason) = validator.validate(topic_id, message) {
+                    return Err(PublishError::SerializationError(format!(
+                        "Message to topic {} is rejected by validator {}: {}",
+                        topic_id,
+                        validator.get_name(),
+                        reason
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn intercept(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+    ) -> Result<Option<Vec<MessageToPublish>>, PublishError> {
+        let interceptors = self.get_interceptors();
+
+        if interceptors.is_empty() {
+            return Ok(None);
+        }
+
+        let mut result = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            let mut message = MessageToPublish {
+                headers: message.headers.clone(),
+                content: message.content.clone(),
+            };
+
+            for interceptor in &interceptors {
+                if let Err(reason) = interceptor.intercept(topic_id, &mut message).await {
+                    return Err(PublishError::Other(format!(
+                        "Message to topic {} is rejected by interceptor {}: {}",
+                        topic_id,
+                        interceptor.get_name(),
+                        reason
+                    )));
+                }
+            }
+
+            result.push(message);
+        }
+
+        Ok(Some(result))
+    }
+
+    // Returns rewritten messages if they had to be changed and the persist immediately flag
+    async fn prepare_messages(
+        &self,
+        topic_id: &str,
+        settings: &PublisherSettings,
+        messages: &[MessageToPublish],
+    ) -> Result<(Option<Vec<MessageToPublish>>, bool), PublishError> {
+        self.validate(topic_id, messages)?;
+
+        let mut prepared = self.intercept(topic_id, messages).await?;
+
+        if settings.stamp_message_headers {
+            let source = prepared.as_deref().unwrap_or(messages);
+            prepared = Some(self.headers_stamper.stamp(source));
+        }
+
+        let mut persist_immediately = settings.persist_immediately;
+
+        let source = prepared.as_deref().unwrap_or(messages);
+
+        if has_persist_immediately_header(source) {
+            let (requested, messages) = take_persist_immediately_header(source);
+            persist_immediately = persist_immediately || requested;
+            prepared = Some(messages);
+        }
+
+        Ok((prepared, persist_immediately))
+    }
+
+    pub async fn set_failure_handler(
+        &self,
+        handler: Arc<dyn PublishFailureHandler + Send + Sync + 'static>,
+    ) {
+        let mut write_access = self.data.write().unwrap();
+        write_access.failure_handler = Some(handler);
+    }
+
+    async fn handle_failure(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        error: &PublishError,
+    ) {
+        let handler = {
+            let read_access = self.data.read().unwrap();
+            read_access.failure_handler.clone()
+        };
+
+        if let Some(handler) = handler {
+            handler.on_publish_failed(topic_id, messages, error).await;
+        }
+    }
+
+    // Packet is only handed to the socket; PublishResponse for it is ignored
+    pub async fn publish_fire_and_forget(
         &self,
         topic_id: &str,
         messages: &[MessageToPublish],
-        do_retries: bool,
     ) -> Result<(), PublishError> {
-        let mut to_send = None;
+        let settings = self.get_publisher_settings(topic_id);
+        let metrics = self.metrics.get(topic_id);
 
-        loop {
-            let awaiter_result = {
-                let mut write_access = self.data.lock().await;
+        let _ordered = if settings.ordered {
+            Some(self.ordered_queues.enter(topic_id).await)
+        } else {
+            None
+        };
 
-                let result = if to_send.is_none() {
-                    let result = write_access
-                        .compile_publish_payload(topic_id, messages)
-                        .await;
+        let (prepared, persist_immediately) =
+            match self.prepare_messages(topic_id, &settings, messages).await {
+                Ok(result) => result,
+                // Rejected messages would be rejected again on a replay, so they skip the failure handler
+                Err(err) => {
+                    metrics.fire_and_forget_failed(messages.len());
+                    return Err(err);
+                }
+            };
 
-                    match result {
-                        Ok(result) => {
-                            to_send = Some(result);
-                            Ok(())
-                        }
-                        Err(err) => Err(err),
-                    }
-                } else {
-                    Ok(())
-                };
+        let messages = prepared.as_deref().unwrap_or(messages);
 
-                match result {
-                    Ok(_) => {
-                        let (request_id, tcp_contract) = to_send.as_ref().unwrap();
+        let chunks = match self.get_publish_chunk_settings() {
+            Some(chunk_settings) => chunk_settings.split(messages),
+            None => vec![messages],
+        };
 
-                        let awaiter = write_access
-                            .publish_to_socket(tcp_contract, *request_id)
-                            .await;
+        let mut sent = 0;
 
-                        Ok(awaiter)
-                    }
-                    Err(err) => Err(err),
+        for chunk in chunks {
+            if let Some(rate_limit) = settings.rate_limit.as_ref() {
+                if let Err(err) = self.wait_for_rate_limit(topic_id, rate_limit, chunk).await {
+                    metrics.fire_and_forget_failed(messages.len() - sent);
+                    self.handle_failure(topic_id, &messages[sent..], &err).await;
+                    return Err(err);
+                }
+            }
+
+            let connection = match self.get_connection() {
+                Some(connection) => connection,
+                None => {
+                    let err = PublishError::NoConnectionToPublish;
+                    metrics.fire_and_forget_failed(messages.len() - sent);
+                    self.handle_failure(topic_id, &messages[sent..], &err).await;
+                    return Err(err);
                 }
             };
 
-            let result = match awaiter_result {
-                Ok(awaiter) => awaiter.get_result().await,
-                Err(err) => Err(err),
+            let packet = PublishPacket::compile(
+                topic_id,
+                self.get_next_request_id(),
+                chunk,
+                persist_immediately,
+            );
+
+            connection.send(&packet).await;
+            metrics.fire_and_forget_sent(chunk.len());
+            sent += chunk.len();
+        }
+
+        Ok(())
+    }
+
+    pub async fn publish_messages_in_chunks(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        do_retries: bool,
+        retry_mode: ChunkRetryMode,
+    ) -> Result<(), PublishChunkError> {
+        let settings = self.get_publisher_settings(topic_id);
+
+        let _ordered = if settings.ordered {
+            Some(self.ordered_queues.enter(topic_id).await)
+        } else {
+            None
+        };
+
+        let (prepared, persist_immediately) =
+            match self.prepare_messages(topic_id, &settings, messages).await {
+                Ok(result) => result,
+                // Rejected messages would be rejected again on a replay, so they skip the failure handler
+                Err(error) => {
+                    self.metrics.get(topic_id).failed();
+
+                    // Nothing is split nor sent yet
+                    return Err(PublishChunkError {
+                        chunk_no: 0,
+                        chunks_amount: 0,
+                        error,
+                    });
+                }
             };
 
-            if result.is_ok() {
-                return Ok(());
+        let messages = prepared.as_deref().unwrap_or(messages);
+
+        let chunks = match self.get_publish_chunk_settings() {
+            Some(chunk_settings) => chunk_settings.split(messages),
+            None => vec![messages],
+        };
+
+        let chunks_amount = chunks.len();
+
+        let mut chunk_no = 0;
+
+        while chunk_no < chunks_amount {
+            if let Some(rate_limit) = settings.rate_limit.as_ref() {
+                let result = self
+                    .wait_for_rate_limit(topic_id, rate_limit, chunks[chunk_no])
+                    .await;
+
+                if let Err(error) = result {
+                    let published: usize = chunks[..chunk_no].iter().map(|c| c.len()).sum();
+                    self.handle_failure(topic_id, &messages[published..], &error)
+                        .await;
+
+                    return Err(PublishChunkError {
+                        chunk_no,
+                        chunks_amount,
+                        error,
+                    });
+                }
             }
 
-            if !do_retries {
-                return result;
+            let do_chunk_retries =
+                do_retries && retry_mode == ChunkRetryMode::ResumeFromFailedChunk;
+
+            let result = self
+                .publish_with_retries(
+                    topic_id,
+                    chunks[chunk_no],
+                    persist_immediately,
+                    do_chunk_retries,
+                )
+                .await;
+
+            match result {
+                Ok(_) => chunk_no += 1,
+                Err(PublishError::NoConnectionToPublish) | Err(PublishError::Disconnected)
+                    if do_retries && retry_mode == ChunkRetryMode::RestartWholeBatch =>
+                {
+                    self.metrics.get(topic_id).retried();
+                    self.wait_until_connection_is_restored().await;
+                    chunk_no = 0;
+                }
+                Err(error) => {
+                    self.metrics.get(topic_id).failed();
+
+                    let published: usize = chunks[..chunk_no].iter().map(|c| c.len()).sum();
+                    self.handle_failure(topic_id, &messages[published..], &error)
+                        .await;
+
+                    return Err(PublishChunkError {
+                        chunk_no,
+                        chunks_amount,
+                        error,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Batches are published concurrently, so their packets are pipelined over the connection.
+    // Results are returned in the order of the batches.
+    pub async fn publish_multi(
+        &self,
+        batches: &[(&str, &[MessageToPublish])],
+        do_retries: bool,
+    ) -> Vec<(String, Result<(), PublishError>)> {
+        let results = futures::future::join_all(
+            batches
+                .iter()
+                .map(|(topic_id, messages)| self.publish_messages(topic_id, messages, do_retries)),
+        )
+        .await;
+
+        batches
+            .iter()
+            .zip(results)
+            .map(|((topic_id, _), result)| (topic_id.to_string(), result))
+            .collect()
+    }
+
+    // Dropping the publish future stops retries and removes its request from the pending map
+    pub async fn publish_messages_with_cancellation(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        do_retries: bool,
+        cancellation_token: &CancellationToken,
+    ) -> Result<(), CancellablePublishError> {
+        tokio::select! {
+            biased;
+            _ = cancellation_token.cancelled() => Err(CancellablePublishError::Cancelled),
+            result = self.publish_messages(topic_id, messages, do_retries) => {
+                result.map_err(CancellablePublishError::PublishError)
             }
+        }
+    }
+
+    // Failure is counted by the caller, since a failed chunk of a batch can still be restarted
+    async fn publish_with_retries(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        persist_immediately: bool,
+        do_retries: bool,
+    ) -> Result<(), PublishError> {
+        let metrics = self.metrics.get(topic_id);
+
+        let mut to_send = None;
+
+        loop {
+            let result = self
+                .publish_packet(topic_id, messages, persist_immediately, &mut to_send)
+                .await;
 
             if result.is_ok() {
                 return Ok(());
             }
 
+            if !do_retries {
+                return result;
+            }
+
             match result.unwrap_err() {
                 PublishError::NoConnectionToPublish => {
+                    metrics.retried();
                     self.wait_until_connection_is_restored().await;
                 }
                 PublishError::Disconnected => {
+                    metrics.retried();
                     self.wait_until_connection_is_restored().await;
                 }
                 PublishError::Other(other) => {
@@ -172,3 +798,36 @@ impl MyServiceBusPublisherClient for MySbPublishers {
         }
     }
 }
+
+#[async_trait::async_trait]
+impl MyServiceBusPublisherClient for MySbPublishers {
+    async fn publish_message(
+        &self,
+        topic_id: &str,
+        message: MessageToPublish,
+        do_retry: bool,
+    ) -> Result<(), PublishError> {
+        return self.publish_messages(topic_id, &[message], do_retry).await;
+    }
+
+    async fn publish_messages(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        do_retries: bool,
+    ) -> Result<(), PublishError> {
+        let result = self
+            .publish_messages_in_chunks(
+                topic_id,
+                messages,
+                do_retries,
+                ChunkRetryMode::ResumeFromFailedChunk,
+            )
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.error),
+        }
+    }
+}
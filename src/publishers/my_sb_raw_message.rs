@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use my_service_bus_abstractions::publisher::MySbMessageSerializer;
+
+pub struct MySbRawMessage {
+    pub content: Vec<u8>,
+    pub headers: Option<HashMap<String, String>>,
+}
+
+impl MySbRawMessage {
+    pub fn new(content: Vec<u8>) -> Self {
+        Self {
+            content,
+            headers: None,
+        }
+    }
+
+    pub fn with_headers(content: Vec<u8>, headers: HashMap<String, String>) -> Self {
+        Self {
+            content,
+            headers: Some(headers),
+        }
+    }
+}
+
+impl MySbMessageSerializer for MySbRawMessage {
+    fn serialize(
+        &self,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<(Vec<u8>, Option<HashMap<String, String>>), String> {
+        let headers = match (headers, self.headers.as_ref()) {
+            (Some(mut headers), Some(own_headers)) => {
+                for (key, value) in own_headers {
+                    headers.insert(key.to_string(), value.to_string());
+                }
+                Some(headers)
+            }
+            (Some(headers), None) => Some(headers),
+            (None, own_headers) => own_headers.cloned(),
+        };
+
+        Ok((self.content.clone(), headers))
+    }
+}
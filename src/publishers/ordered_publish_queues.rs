@@ -0,0 +1,31 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::OwnedMutexGuard;
+
+// tokio Mutex is fair, so publishes of a topic get through it in the order they were called
+pub struct OrderedPublishQueues {
+    topics: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl OrderedPublishQueues {
+    pub fn new() -> Self {
+        Self {
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn enter(&self, topic_id: &str) -> OwnedMutexGuard<()> {
+        let queue = {
+            let mut write_access = self.topics.lock().unwrap();
+            write_access
+                .entry(topic_id.to_string())
+                .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(())))
+                .clone()
+        };
+
+        queue.lock_owned().await
+    }
+}
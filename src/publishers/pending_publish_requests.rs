@@ -0,0 +1,57 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use my_service_bus_abstractions::PublishError;
+use rust_extensions::TaskCompletion;
+
+const SHARDS_AMOUNT: usize = 16;
+
+pub struct PublishRequest {
+    pub task: TaskCompletion<(), PublishError>,
+    pub payload: Arc<Vec<u8>>,
+    pub payload_size: usize,
+}
+
+pub struct PendingPublishRequests {
+    shards: Vec<Mutex<HashMap<i64, PublishRequest>>>,
+}
+
+impl PendingPublishRequests {
+    pub fn new() -> Self {
+        let mut shards = Vec::with_capacity(SHARDS_AMOUNT);
+
+        for _ in 0..SHARDS_AMOUNT {
+            shards.push(Mutex::new(HashMap::new()));
+        }
+
+        Self { shards }
+    }
+
+    fn get_shard(&self, request_id: i64) -> &Mutex<HashMap<i64, PublishRequest>> {
+        let index = request_id.rem_euclid(SHARDS_AMOUNT as i64) as usize;
+        &self.shards[index]
+    }
+
+    pub fn insert(&self, request_id: i64, request: PublishRequest) {
+        let mut write_access = self.get_shard(request_id).lock().unwrap();
+        write_access.insert(request_id, request);
+    }
+
+    pub fn remove(&self, request_id: i64) -> Option<PublishRequest> {
+        let mut write_access = self.get_shard(request_id).lock().unwrap();
+        write_access.remove(&request_id)
+    }
+
+    pub fn drain(&self) -> Vec<(i64, PublishRequest)> {
+        let mut result = Vec::new();
+
+        for shard in &self.shards {
+            let mut write_access = shard.lock().unwrap();
+            result.extend(write_access.drain());
+        }
+
+        result
+    }
+}
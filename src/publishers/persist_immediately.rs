@@ -0,0 +1,43 @@
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+// Per call override for publishers created outside of this crate (MyServiceBusPublisher, PublisherWithInternalQueue).
+// Header is removed before the message goes to the broker.
+pub const PERSIST_IMMEDIATELY_HEADER: &str = "my-sb-persist-immediately";
+
+pub fn has_persist_immediately_header(messages: &[MessageToPublish]) -> bool {
+    messages
+        .iter()
+        .any(|message| match message.headers.as_ref() {
+            Some(headers) => headers.contains_key(PERSIST_IMMEDIATELY_HEADER),
+            None => false,
+        })
+}
+
+pub fn take_persist_immediately_header(
+    messages: &[MessageToPublish],
+) -> (bool, Vec<MessageToPublish>) {
+    let mut persist_immediately = false;
+
+    let messages = messages
+        .iter()
+        .map(|message| {
+            let headers = match message.headers.as_ref() {
+                Some(headers) => {
+                    let mut headers = headers.clone();
+                    if let Some(value) = headers.remove(PERSIST_IMMEDIATELY_HEADER) {
+                        persist_immediately = persist_immediately || value == "true";
+                    }
+                    Some(headers)
+                }
+                None => None,
+            };
+
+            MessageToPublish {
+                headers,
+                content: message.content.clone(),
+            }
+        })
+        .collect();
+
+    (persist_immediately, messages)
+}
@@ -0,0 +1,56 @@
+use std::sync::{Arc, Weak};
+
+use my_service_bus_abstractions::PublishError;
+
+use super::{MySbPublishers, PublishProcessByConnection};
+
+#[derive(Debug)]
+pub enum CancellablePublishError {
+    Cancelled,
+    PublishError(PublishError),
+}
+
+impl From<PublishError> for CancellablePublishError {
+    fn from(err: PublishError) -> Self {
+        Self::PublishError(err)
+    }
+}
+
+// Removes the request from the pending map if the publish future is dropped before the broker answers.
+// Packet which is already written to the socket can still be accepted by the broker.
+// Connection is held weakly, so dropping it on disconnect can still fail the request.
+pub struct PendingPublishGuard<'s> {
+    publishers: &'s MySbPublishers,
+    connection: Weak<PublishProcessByConnection>,
+    request_id: i64,
+    completed: bool,
+}
+
+impl<'s> PendingPublishGuard<'s> {
+    pub fn new(
+        publishers: &'s MySbPublishers,
+        connection: &Arc<PublishProcessByConnection>,
+        request_id: i64,
+    ) -> Self {
+        Self {
+            publishers,
+            connection: Arc::downgrade(connection),
+            request_id,
+            completed: false,
+        }
+    }
+
+    pub fn complete(&mut self) {
+        self.completed = true;
+    }
+}
+
+impl<'s> Drop for PendingPublishGuard<'s> {
+    fn drop(&mut self) {
+        if !self.completed {
+            let connection = self.connection.upgrade();
+            self.publishers
+                .remove_pending_request(connection.as_deref(), self.request_id);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use my_service_bus_abstractions::{publisher::MessageToPublish, PublishError};
+
+#[derive(Debug, Clone)]
+pub struct PublishChunkSettings {
+    pub max_messages: usize,
+    pub max_bytes: usize,
+}
+
+impl PublishChunkSettings {
+    pub fn split<'s>(&self, messages: &'s [MessageToPublish]) -> Vec<&'s [MessageToPublish]> {
+        let mut result = Vec::new();
+
+        let mut chunk_start = 0;
+        let mut chunk_size = 0;
+
+        for (index, message) in messages.iter().enumerate() {
+            let message_size = get_message_size(message);
+
+            let chunk_len = index - chunk_start;
+
+            if chunk_len > 0
+                && (chunk_len >= self.max_messages || chunk_size + message_size > self.max_bytes)
+            {
+                result.push(&messages[chunk_start..index]);
+                chunk_start = index;
+                chunk_size = 0;
+            }
+
+            chunk_size += message_size;
+        }
+
+        if chunk_start < messages.len() || result.is_empty() {
+            result.push(&messages[chunk_start..]);
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkRetryMode {
+    ResumeFromFailedChunk,
+    // Chunks confirmed before the disconnect are published once again, so their messages
+    // are delivered more than once and subscribers have to deduplicate them
+    RestartWholeBatch,
+}
+
+#[derive(Debug)]
+pub struct PublishChunkError {
+    pub chunk_no: usize,
+    pub chunks_amount: usize,
+    pub error: PublishError,
+}
+
+pub fn get_message_size(message: &MessageToPublish) -> usize {
+    let mut result = message.content.len();
+
+    if let Some(headers) = message.headers.as_ref() {
+        for (key, value) in headers {
+            result += key.len() + value.len();
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use my_service_bus_abstractions::publisher::MessageToPublish;
+
+    use super::PublishChunkSettings;
+
+    fn create_message(size: usize) -> MessageToPublish {
+        MessageToPublish {
+            headers: None,
+            content: vec![0; size],
+        }
+    }
+
+    fn split_lens(settings: &PublishChunkSettings, messages: &[MessageToPublish]) -> Vec<usize> {
+        settings
+            .split(messages)
+            .iter()
+            .map(|chunk| chunk.len())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_by_messages_amount() {
+        let settings = PublishChunkSettings {
+            max_messages: 2,
+            max_bytes: 1024,
+        };
+
+        let messages: Vec<_> = (0..5).map(|_| create_message(1)).collect();
+
+        assert_eq!(vec![2, 2, 1], split_lens(&settings, &messages));
+    }
+
+    #[test]
+    fn test_split_by_bytes() {
+        let settings = PublishChunkSettings {
+            max_messages: 100,
+            max_bytes: 10,
+        };
+
+        let messages = vec![
+            create_message(4),
+            create_message(6),
+            create_message(1),
+            create_message(10),
+        ];
+
+        assert_eq!(vec![2, 1, 1], split_lens(&settings, &messages));
+    }
+
+    #[test]
+    fn test_oversized_message_goes_in_its_own_chunk() {
+        let settings = PublishChunkSettings {
+            max_messages: 100,
+            max_bytes: 10,
+        };
+
+        let messages = vec![create_message(2), create_message(50), create_message(2)];
+
+        assert_eq!(vec![1, 1, 1], split_lens(&settings, &messages));
+    }
+
+    #[test]
+    fn test_empty_messages_give_one_empty_chunk() {
+        let settings = PublishChunkSettings {
+            max_messages: 2,
+            max_bytes: 10,
+        };
+
+        assert_eq!(vec![0], split_lens(&settings, &[]));
+    }
+
+    #[test]
+    fn test_headers_are_counted_in_message_size() {
+        let settings = PublishChunkSettings {
+            max_messages: 100,
+            max_bytes: 10,
+        };
+
+        let mut headers = std::collections::HashMap::new();
+        headers.insert("key".to_string(), "value".to_string());
+
+        let messages = vec![
+            MessageToPublish {
+                headers: Some(headers),
+                content: vec![0; 2],
+            },
+            create_message(1),
+        ];
+
+        assert_eq!(vec![1, 1], split_lens(&settings, &messages));
+    }
+}
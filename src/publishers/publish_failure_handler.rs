@@ -0,0 +1,13 @@
+use my_service_bus_abstractions::{publisher::MessageToPublish, PublishError};
+
+#[async_trait::async_trait]
+pub trait PublishFailureHandler {
+    // Messages are the ones which did not reach the broker, with headers as they were sent.
+    // Messages rejected by a validator or an interceptor are not passed here.
+    async fn on_publish_failed(
+        &self,
+        topic_id: &str,
+        messages: &[MessageToPublish],
+        error: &PublishError,
+    );
+}
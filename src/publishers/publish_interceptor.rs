@@ -0,0 +1,10 @@
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+#[async_trait::async_trait]
+pub trait PublishInterceptor {
+    fn get_name(&self) -> &str;
+
+    // Err rejects the whole publish call; nothing is sent to the broker
+    async fn intercept(&self, topic_id: &str, message: &mut MessageToPublish)
+        -> Result<(), String>;
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+pub const LATENCY_BUCKETS_MICROS: [u64; 12] = [
+    500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000,
+    5_000_000,
+];
+
+#[derive(Debug, Clone)]
+pub struct LatencyHistogramSnapshot {
+    // (upper bound in microseconds, amount of packets), the last bucket has u64::MAX as upper bound
+    pub buckets: Vec<(u64, u64)>,
+    pub count: u64,
+    pub sum_micros: u64,
+}
+
+struct LatencyHistogram {
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl LatencyHistogram {
+    fn new() -> Self {
+        let mut buckets = Vec::with_capacity(LATENCY_BUCKETS_MICROS.len() + 1);
+
+        for _ in 0..LATENCY_BUCKETS_MICROS.len() + 1 {
+            buckets.push(AtomicU64::new(0));
+        }
+
+        Self {
+            buckets,
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+
+        let index = LATENCY_BUCKETS_MICROS
+            .iter()
+            .position(|upper_bound| micros <= *upper_bound)
+            .unwrap_or(LATENCY_BUCKETS_MICROS.len());
+
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    fn get_snapshot(&self) -> LatencyHistogramSnapshot {
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(index, bucket)| {
+                let upper_bound = LATENCY_BUCKETS_MICROS
+                    .get(index)
+                    .copied()
+                    .unwrap_or(u64::MAX);
+                (upper_bound, bucket.load(Ordering::Relaxed))
+            })
+            .collect();
+
+        LatencyHistogramSnapshot {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum_micros: self.sum_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TopicPublishMetricsSnapshot {
+    pub topic_id: String,
+    pub published_messages: u64,
+    pub published_packets: u64,
+    pub retries: u64,
+    pub failures: u64,
+    pub throttled: u64,
+    pub rate_limited: u64,
+    pub fire_and_forget_sent: u64,
+    pub fire_and_forget_failed: u64,
+    pub latency: LatencyHistogramSnapshot,
+}
+
+pub struct TopicPublishMetrics {
+    topic_id: String,
+    published_messages: AtomicU64,
+    published_packets: AtomicU64,
+    retries: AtomicU64,
+    failures: AtomicU64,
+    throttled: AtomicU64,
+    rate_limited: AtomicU64,
+    fire_and_forget_sent: AtomicU64,
+    fire_and_forget_failed: AtomicU64,
+    latency: LatencyHistogram,
+}
+
+impl TopicPublishMetrics {
+    fn new(topic_id: String) -> Self {
+        Self {
+            topic_id,
+            published_messages: AtomicU64::new(0),
+            published_packets: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            throttled: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+            fire_and_forget_sent: AtomicU64::new(0),
+            fire_and_forget_failed: AtomicU64::new(0),
+            latency: LatencyHistogram::new(),
+        }
+    }
+
+    pub fn published(&self, messages_amount: usize, latency: Duration) {
+        self.published_messages
+            .fetch_add(messages_amount as u64, Ordering::Relaxed);
+        self.published_packets.fetch_add(1, Ordering::Relaxed);
+        self.latency.record(latency);
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("my_sb_published_messages", messages_amount as u64, "topic" => self.topic_id.clone());
+            metrics::counter!("my_sb_published_packets", 1, "topic" => self.topic_id.clone());
+            metrics::histogram!("my_sb_publish_latency_seconds", latency.as_secs_f64(), "topic" => self.topic_id.clone());
+        }
+    }
+
+    pub fn retried(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("my_sb_publish_retries", 1, "topic" => self.topic_id.clone());
+    }
+
+    pub fn failed(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("my_sb_publish_failures", 1, "topic" => self.topic_id.clone());
+    }
+
+    pub fn throttled(&self) {
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("my_sb_publish_throttled", 1, "topic" => self.topic_id.clone());
+    }
+
+    pub fn rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("my_sb_publish_rate_limited", 1, "topic" => self.topic_id.clone());
+    }
+
+    pub fn fire_and_forget_sent(&self, messages_amount: usize) {
+        self.fire_and_forget_sent
+            .fetch_add(messages_amount as u64, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("my_sb_fire_and_forget_sent", messages_amount as u64, "topic" => self.topic_id.clone());
+    }
+
+    pub fn fire_and_forget_failed(&self, messages_amount: usize) {
+        self.fire_and_forget_failed
+            .fetch_add(messages_amount as u64, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("my_sb_fire_and_forget_failed", messages_amount as u64, "topic" => self.topic_id.clone());
+    }
+
+    pub fn get_snapshot(&self) -> TopicPublishMetricsSnapshot {
+        TopicPublishMetricsSnapshot {
+            topic_id: self.topic_id.clone(),
+            published_messages: self.published_messages.load(Ordering::Relaxed),
+            published_packets: self.published_packets.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            fire_and_forget_sent: self.fire_and_forget_sent.load(Ordering::Relaxed),
+            fire_and_forget_failed: self.fire_and_forget_failed.load(Ordering::Relaxed),
+            latency: self.latency.get_snapshot(),
+        }
+    }
+}
+
+pub struct PublishMetrics {
+    topics: RwLock<HashMap<String, Arc<TopicPublishMetrics>>>,
+}
+
+impl PublishMetrics {
+    pub fn new() -> Self {
+        Self {
+            topics: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, topic_id: &str) -> Arc<TopicPublishMetrics> {
+        {
+            let read_access = self.topics.read().unwrap();
+            if let Some(result) = read_access.get(topic_id) {
+                return result.clone();
+            }
+        }
+
+        let mut write_access = self.topics.write().unwrap();
+        write_access
+            .entry(topic_id.to_string())
+            .or_insert_with(|| Arc::new(TopicPublishMetrics::new(topic_id.to_string())))
+            .clone()
+    }
+
+    pub fn get_snapshot(&self) -> Vec<TopicPublishMetricsSnapshot> {
+        let read_access = self.topics.read().unwrap();
+        read_access
+            .values()
+            .map(|metrics| metrics.get_snapshot())
+            .collect()
+    }
+}
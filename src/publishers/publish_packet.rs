@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use my_service_bus_abstractions::publisher::MessageToPublish;
+use my_service_bus_tcp_shared::TcpContract;
+
+use crate::new_connection_handler::PROTOCOL_VERSION;
+
+pub struct PublishPacket {
+    pub request_id: i64,
+    pub payload: Arc<Vec<u8>>,
+    pub payload_size: usize,
+}
+
+impl PublishPacket {
+    pub fn compile(
+        topic_id: &str,
+        request_id: i64,
+        messages: &[MessageToPublish],
+        persist_immediately: bool,
+    ) -> Self {
+        let payload = TcpContract::compile_publish_payload(
+            topic_id,
+            request_id,
+            messages,
+            persist_immediately,
+            PROTOCOL_VERSION,
+        );
+
+        Self {
+            request_id,
+            payload_size: payload.len(),
+            payload: Arc::new(payload),
+        }
+    }
+}
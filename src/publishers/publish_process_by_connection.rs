@@ -1,28 +1,141 @@
-use std::{collections::HashMap, sync::Arc};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
 
 use my_service_bus_abstractions::PublishError;
 use my_service_bus_tcp_shared::{MySbTcpSerializer, TcpContract};
 use my_tcp_sockets::tcp_connection::SocketConnection;
-use rust_extensions::TaskCompletion;
+use rust_extensions::{TaskCompletion, TaskCompletionAwaiter};
+
+use super::{InFlightWindowSettings, PendingPublishRequests, PublishPacket, PublishRequest};
 
 pub struct PublishProcessByConnection {
     pub socket: Arc<SocketConnection<TcpContract, MySbTcpSerializer>>,
-    pub requests: HashMap<i64, TaskCompletion<(), PublishError>>,
+    requests: PendingPublishRequests,
+    in_flight_requests: AtomicUsize,
+    in_flight_bytes: AtomicUsize,
+    reject_reason: Mutex<Option<String>>,
 }
 
 impl PublishProcessByConnection {
     pub fn new(socket: Arc<SocketConnection<TcpContract, MySbTcpSerializer>>) -> Self {
         Self {
-            requests: HashMap::new(),
+            requests: PendingPublishRequests::new(),
             socket,
+            in_flight_requests: AtomicUsize::new(0),
+            in_flight_bytes: AtomicUsize::new(0),
+            reject_reason: Mutex::new(None),
+        }
+    }
+
+    pub fn try_acquire_in_flight(
+        &self,
+        window: Option<&InFlightWindowSettings>,
+        payload_size: usize,
+    ) -> bool {
+        let requests = self.in_flight_requests.fetch_add(1, Ordering::SeqCst);
+        let bytes = self
+            .in_flight_bytes
+            .fetch_add(payload_size, Ordering::SeqCst);
+
+        if let Some(window) = window {
+            if !window.has_room(requests, bytes, payload_size) {
+                self.release_in_flight(payload_size);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn release_in_flight(&self, payload_size: usize) {
+        self.in_flight_requests.fetch_sub(1, Ordering::SeqCst);
+        self.in_flight_bytes
+            .fetch_sub(payload_size, Ordering::SeqCst);
+    }
+
+    // In-flight slot has to be acquired before calling this method
+    pub async fn publish(&self, packet: &PublishPacket) -> TaskCompletionAwaiter<(), PublishError> {
+        let mut task = TaskCompletion::new();
+        let awaiter = task.get_awaiter();
+
+        // Request is registered before the write so a fast PublishResponse can always find it
+        self.requests.insert(
+            packet.request_id,
+            PublishRequest {
+                task,
+                payload: packet.payload.clone(),
+                payload_size: packet.payload_size,
+            },
+        );
+
+        self.socket.send_bytes(packet.payload.as_slice()).await;
+
+        awaiter
+    }
+
+    pub async fn send(&self, packet: &PublishPacket) {
+        self.socket.send_bytes(packet.payload.as_slice()).await;
+    }
+
+    // Resent requests bypass the in-flight window, they were accepted by it on the previous connection
+    pub async fn resend(&self, request_id: i64, request: PublishRequest) {
+        self.try_acquire_in_flight(None, request.payload_size);
+
+        let payload = request.payload.clone();
+        self.requests.insert(request_id, request);
+
+        self.socket.send_bytes(payload.as_slice()).await;
+    }
+
+    pub fn take_all(&self) -> Vec<(i64, PublishRequest)> {
+        let result = self.requests.drain();
+
+        for (_, request) in &result {
+            self.release_in_flight(request.payload_size);
+        }
+
+        result
+    }
+
+    pub fn confirm(&self, request_id: i64) -> bool {
+        match self.requests.remove(request_id) {
+            Some(mut request) => {
+                self.release_in_flight(request.payload_size);
+                request.task.set_ok(());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&self, request_id: i64) {
+        if let Some(request) = self.requests.remove(request_id) {
+            self.release_in_flight(request.payload_size);
+        }
+    }
+
+    pub fn set_rejected(&self, reason: String) {
+        let mut write_access = self.reject_reason.lock().unwrap();
+        *write_access = Some(reason);
+    }
+
+    pub fn get_reject_reason(&self) -> Option<String> {
+        let read_access = self.reject_reason.lock().unwrap();
+        read_access.clone()
+    }
+
+    pub fn fail_all(&self) {
+        for (_, mut request) in self.requests.drain() {
+            self.release_in_flight(request.payload_size);
+            request.task.set_error(PublishError::Disconnected);
         }
     }
 }
 
 impl Drop for PublishProcessByConnection {
     fn drop(&mut self) {
-        for (_, mut task) in self.requests.drain() {
-            task.set_error(PublishError::Disconnected);
-        }
+        self.fail_all();
     }
 }
@@ -0,0 +1,272 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use my_service_bus_abstractions::{publisher::MessageToPublish, MyServiceBusPublisherClient};
+use rust_extensions::Logger;
+use tokio::{sync::Notify, time::Instant};
+
+use super::{MySbPublishers, ScheduledMessage, ScheduledMessagesStore};
+
+struct ScheduledQueue {
+    by_due: BTreeMap<(Instant, u64), ScheduledMessage>,
+    ids: HashSet<String>,
+    seq: u64,
+}
+
+impl ScheduledQueue {
+    fn new() -> Self {
+        Self {
+            by_due: BTreeMap::new(),
+            ids: HashSet::new(),
+            seq: 0,
+        }
+    }
+
+    // Wall clock time is converted to tokio time once, so the scheduler can be driven by paused tokio time
+    fn insert(&mut self, scheduled: ScheduledMessage) -> bool {
+        // Messages scheduled before start are saved to the store and loaded from it once again
+        if !self.ids.insert(scheduled.id.clone()) {
+            return false;
+        }
+
+        let delay = (scheduled.publish_at - Utc::now())
+            .to_std()
+            .unwrap_or_default();
+
+        self.seq += 1;
+        self.by_due
+            .insert((Instant::now() + delay, self.seq), scheduled);
+        true
+    }
+
+    fn get_next_due(&self) -> Option<Instant> {
+        self.by_due.keys().next().map(|(due, _)| *due)
+    }
+
+    fn pop_due(&mut self, now: Instant) -> Vec<ScheduledMessage> {
+        let mut result = Vec::new();
+
+        while let Some(((due, _), _)) = self.by_due.first_key_value() {
+            if *due > now {
+                break;
+            }
+
+            let (_, scheduled) = self.by_due.pop_first().unwrap();
+            self.ids.remove(scheduled.id.as_str());
+            result.push(scheduled);
+        }
+
+        result
+    }
+}
+
+pub struct PublishScheduler {
+    queue: Mutex<ScheduledQueue>,
+    store: Mutex<Option<Arc<dyn ScheduledMessagesStore + Send + Sync + 'static>>>,
+    queue_changed: Notify,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+}
+
+impl PublishScheduler {
+    pub fn new(logger: Arc<dyn Logger + Send + Sync + 'static>) -> Self {
+        Self {
+            queue: Mutex::new(ScheduledQueue::new()),
+            store: Mutex::new(None),
+            queue_changed: Notify::new(),
+            logger,
+        }
+    }
+
+    pub fn set_store(&self, store: Arc<dyn ScheduledMessagesStore + Send + Sync + 'static>) {
+        let mut write_access = self.store.lock().unwrap();
+        *write_access = Some(store);
+    }
+
+    fn get_store(&self) -> Option<Arc<dyn ScheduledMessagesStore + Send + Sync + 'static>> {
+        let read_access = self.store.lock().unwrap();
+        read_access.clone()
+    }
+
+    fn enqueue(&self, scheduled: ScheduledMessage) {
+        let inserted = {
+            let mut write_access = self.queue.lock().unwrap();
+            write_access.insert(scheduled)
+        };
+
+        if inserted {
+            self.queue_changed.notify_waiters();
+        }
+    }
+
+    pub async fn schedule(
+        &self,
+        topic_id: String,
+        message: MessageToPublish,
+        publish_at: DateTime<Utc>,
+    ) -> String {
+        let scheduled = ScheduledMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            topic_id,
+            publish_at,
+            message,
+        };
+
+        if let Some(store) = self.get_store() {
+            store.save(&scheduled).await;
+        }
+
+        let id = scheduled.id.clone();
+        self.enqueue(scheduled);
+        id
+    }
+
+    pub fn get_scheduled_amount(&self) -> usize {
+        let read_access = self.queue.lock().unwrap();
+        read_access.by_due.len()
+    }
+
+    fn get_next_due(&self) -> Option<Instant> {
+        let read_access = self.queue.lock().unwrap();
+        read_access.get_next_due()
+    }
+
+    fn pop_due(&self, now: Instant) -> Vec<ScheduledMessage> {
+        let mut write_access = self.queue.lock().unwrap();
+        write_access.pop_due(now)
+    }
+
+    pub async fn start(self: Arc<Self>, publishers: Arc<MySbPublishers>) {
+        if let Some(store) = self.get_store() {
+            for scheduled in store.load_all().await {
+                self.enqueue(scheduled);
+            }
+        }
+
+        tokio::spawn(async move {
+            self.run(publishers).await;
+        });
+    }
+
+    async fn run(&self, publishers: Arc<MySbPublishers>) {
+        loop {
+            let queue_changed = self.queue_changed.notified();
+
+            match self.get_next_due() {
+                Some(due) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(due) => {}
+                        _ = queue_changed => {
+                            continue;
+                        }
+                    }
+                }
+                None => {
+                    queue_changed.await;
+                    continue;
+                }
+            }
+
+            for scheduled in self.pop_due(Instant::now()) {
+                // Retries keep the message until the connection is back
+                let result = publishers
+                    .publish_messages(
+                        scheduled.topic_id.as_str(),
+                        std::slice::from_ref(&scheduled.message),
+                        true,
+                    )
+                    .await;
+
+                if let Err(err) = result {
+                    let mut ctx = HashMap::new();
+                    ctx.insert("TopicId".to_string(), scheduled.topic_id.clone());
+                    ctx.insert("ScheduledMessageId".to_string(), scheduled.id.clone());
+
+                    self.logger.write_error(
+                        "PublishScheduler".to_string(),
+                        format!("Scheduled message can not be published: {:?}", err),
+                        Some(ctx),
+                    );
+                }
+
+                if let Some(store) = self.get_store() {
+                    store.remove(scheduled.id.as_str()).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use my_service_bus_abstractions::publisher::MessageToPublish;
+    use tokio::time::Instant;
+
+    use super::ScheduledQueue;
+    use crate::publishers::ScheduledMessage;
+
+    fn create_scheduled(id: &str, delay_secs: i64) -> ScheduledMessage {
+        ScheduledMessage {
+            id: id.to_string(),
+            topic_id: "test-topic".to_string(),
+            publish_at: Utc::now() + chrono::Duration::seconds(delay_secs),
+            message: MessageToPublish {
+                headers: None,
+                content: vec![],
+            },
+        }
+    }
+
+    fn get_ids(scheduled: Vec<ScheduledMessage>) -> Vec<String> {
+        scheduled
+            .into_iter()
+            .map(|scheduled| scheduled.id)
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_messages_are_due_in_publish_at_order() {
+        let mut queue = ScheduledQueue::new();
+
+        queue.insert(create_scheduled("late", 5));
+        queue.insert(create_scheduled("early", 2));
+
+        assert!(queue.pop_due(Instant::now()).is_empty());
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        assert_eq!(vec!["early"], get_ids(queue.pop_due(Instant::now())));
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        assert_eq!(vec!["late"], get_ids(queue.pop_due(Instant::now())));
+
+        assert!(queue.get_next_due().is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_message_in_the_past_is_due_right_away() {
+        let mut queue = ScheduledQueue::new();
+
+        queue.insert(create_scheduled("past", -10));
+
+        assert_eq!(vec!["past"], get_ids(queue.pop_due(Instant::now())));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_message_with_the_same_id_is_scheduled_once() {
+        let mut queue = ScheduledQueue::new();
+
+        assert!(queue.insert(create_scheduled("id", 1)));
+        assert!(!queue.insert(create_scheduled("id", 1)));
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(vec!["id"], get_ids(queue.pop_due(Instant::now())));
+
+        // Id can be used again once the message is published
+        assert!(queue.insert(create_scheduled("id", 1)));
+    }
+}
@@ -0,0 +1,15 @@
+use super::PublishRateLimit;
+
+#[derive(Debug, Clone, Default)]
+pub struct PublisherSettings {
+    pub stamp_message_headers: bool,
+    pub persist_immediately: bool,
+    pub rate_limit: Option<PublishRateLimit>,
+    // Publishes are sequenced per topic; a failing packet holds the queue while the caller retries it
+    pub ordered: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublisherSettingsError {
+    ZeroRateLimit { topic_id: String },
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use my_service_bus_abstractions::PublishError;
+use tokio::time::Instant;
+
+// Prefix of the PublishError::Other message returned when the rate limit is exceeded
+pub const RATE_LIMIT_EXCEEDED: &str = "Publish rate limit exceeded";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitExceededBehavior {
+    Wait,
+    // Publish fails with PublishError::Other starting with RATE_LIMIT_EXCEEDED, see is_rate_limit_exceeded
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct PublishRateLimit {
+    pub messages_per_second: Option<u64>,
+    pub bytes_per_second: Option<u64>,
+    pub when_exceeded: RateLimitExceededBehavior,
+}
+
+impl PublishRateLimit {
+    // Zero rate would let everything through instead of blocking publishes
+    pub fn has_zero_rate(&self) -> bool {
+        self.messages_per_second == Some(0) || self.bytes_per_second == Some(0)
+    }
+}
+
+pub fn is_rate_limit_exceeded(err: &PublishError) -> bool {
+    match err {
+        PublishError::Other(message) => message.starts_with(RATE_LIMIT_EXCEEDED),
+        _ => false,
+    }
+}
+
+pub fn rate_limit_exceeded_error(topic_id: &str) -> PublishError {
+    PublishError::Other(format!("{}. Topic: {}", RATE_LIMIT_EXCEEDED, topic_id))
+}
+
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled = now;
+    }
+
+    // Amount bigger than one second of rate goes through once the bucket is full and leaves it in debt
+    fn get_wait(&self, amount: usize) -> Option<Duration> {
+        let needed = (amount as f64).min(self.rate);
+
+        if self.tokens >= needed {
+            return None;
+        }
+
+        Some(Duration::from_secs_f64((needed - self.tokens) / self.rate))
+    }
+
+    fn take(&mut self, amount: usize) {
+        self.tokens -= amount as f64;
+    }
+}
+
+struct TopicRateLimiterState {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+pub struct TopicRateLimiter {
+    state: Mutex<TopicRateLimiterState>,
+}
+
+impl TopicRateLimiter {
+    fn new(limit: &PublishRateLimit) -> Self {
+        Self {
+            state: Mutex::new(TopicRateLimiterState {
+                messages: limit.messages_per_second.map(TokenBucket::new),
+                bytes: limit.bytes_per_second.map(TokenBucket::new),
+            }),
+        }
+    }
+
+    pub fn try_acquire(&self, messages: usize, bytes: usize) -> Result<(), Duration> {
+        let mut write_access = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let mut wait = None;
+
+        if let Some(bucket) = write_access.messages.as_mut() {
+            bucket.refill(now);
+            wait = wait.max(bucket.get_wait(messages));
+        }
+
+        if let Some(bucket) = write_access.bytes.as_mut() {
+            bucket.refill(now);
+            wait = wait.max(bucket.get_wait(bytes));
+        }
+
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+
+        if let Some(bucket) = write_access.messages.as_mut() {
+            bucket.take(messages);
+        }
+
+        if let Some(bucket) = write_access.bytes.as_mut() {
+            bucket.take(bytes);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RateLimiters {
+    topics: Mutex<HashMap<String, Arc<TopicRateLimiter>>>,
+}
+
+impl RateLimiters {
+    pub fn new() -> Self {
+        Self {
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, topic_id: &str, limit: &PublishRateLimit) -> Arc<TopicRateLimiter> {
+        let mut write_access = self.topics.lock().unwrap();
+        write_access
+            .entry(topic_id.to_string())
+            .or_insert_with(|| Arc::new(TopicRateLimiter::new(limit)))
+            .clone()
+    }
+
+    pub fn reset(&self, topic_id: &str) {
+        let mut write_access = self.topics.lock().unwrap();
+        write_access.remove(topic_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{PublishRateLimit, RateLimitExceededBehavior, TokenBucket};
+
+    fn assert_wait(expected_secs: f64, wait: Option<Duration>) {
+        let wait = wait.unwrap();
+        assert!((wait.as_secs_f64() - expected_secs).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_full_bucket_lets_amount_through() {
+        let bucket = TokenBucket::new(10);
+
+        assert_eq!(None, bucket.get_wait(10));
+    }
+
+    #[test]
+    fn test_empty_bucket_waits_for_refill() {
+        let mut bucket = TokenBucket::new(10);
+        let now = bucket.refilled;
+
+        bucket.take(10);
+        assert_wait(0.1, bucket.get_wait(1));
+
+        bucket.refill(now + Duration::from_millis(50));
+        assert_wait(0.05, bucket.get_wait(1));
+
+        bucket.refill(now + Duration::from_millis(200));
+        assert_eq!(None, bucket.get_wait(1));
+    }
+
+    #[test]
+    fn test_refill_is_capped_by_rate() {
+        let mut bucket = TokenBucket::new(10);
+        let now = bucket.refilled;
+
+        bucket.refill(now + Duration::from_secs(60));
+
+        assert_eq!(10.0, bucket.tokens);
+    }
+
+    #[test]
+    fn test_amount_bigger_than_rate_leaves_bucket_in_debt() {
+        let mut bucket = TokenBucket::new(10);
+
+        assert_eq!(None, bucket.get_wait(30));
+        bucket.take(30);
+
+        assert_wait(2.1, bucket.get_wait(1));
+    }
+
+    #[test]
+    fn test_zero_rate_is_detected() {
+        let mut limit = PublishRateLimit {
+            messages_per_second: Some(10),
+            bytes_per_second: None,
+            when_exceeded: RateLimitExceededBehavior::Wait,
+        };
+
+        assert!(!limit.has_zero_rate());
+
+        limit.bytes_per_second = Some(0);
+        assert!(limit.has_zero_rate());
+    }
+}
@@ -0,0 +1,34 @@
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+use super::MessageValidator;
+
+pub struct RequiredHeadersValidator {
+    headers: Vec<String>,
+}
+
+impl RequiredHeadersValidator {
+    pub fn new(headers: Vec<String>) -> Self {
+        Self { headers }
+    }
+}
+
+impl MessageValidator for RequiredHeadersValidator {
+    fn get_name(&self) -> &str {
+        "RequiredHeaders"
+    }
+
+    fn validate(&self, _topic_id: &str, message: &MessageToPublish) -> Result<(), String> {
+        for header in &self.headers {
+            let has_header = match message.headers.as_ref() {
+                Some(headers) => headers.contains_key(header),
+                None => false,
+            };
+
+            if !has_header {
+                return Err(format!("Header {} is missing", header));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use my_service_bus_abstractions::publisher::MessageToPublish;
+
+pub struct ScheduledMessage {
+    pub id: String,
+    pub topic_id: String,
+    pub publish_at: DateTime<Utc>,
+    pub message: MessageToPublish,
+}
+
+#[async_trait::async_trait]
+pub trait ScheduledMessagesStore {
+    async fn save(&self, message: &ScheduledMessage);
+    async fn remove(&self, id: &str);
+    async fn load_all(&self) -> Vec<ScheduledMessage>;
+}
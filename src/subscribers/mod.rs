@@ -1,5 +1,13 @@
 mod my_sb_subscribers;
 mod my_sb_subscribers_data;
+mod parallel_subscriber;
+mod raw_messages_reader;
+mod raw_subscriber;
+mod subscribe_options;
 
 pub use my_sb_subscribers::MySbSubscribers;
-pub use my_sb_subscribers_data::MySbSubscribersData;
+pub use my_sb_subscribers_data::{MySbSubscribersData, ParkedBatch, PARKED_BATCH_TIMEOUT};
+pub use parallel_subscriber::*;
+pub use raw_messages_reader::*;
+pub use raw_subscriber::*;
+pub use subscribe_options::*;
@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use my_service_bus_abstractions::{
     MySbMessage, MyServiceBusSubscriberClient, MyServiceBusSubscriberClientCallback,
@@ -7,29 +10,169 @@ use my_service_bus_abstractions::{
 use my_service_bus_tcp_shared::{MySbTcpSerializer, TcpContract};
 
 use my_tcp_sockets::tcp_connection::SocketConnection;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
-use super::MySbSubscribersData;
+use super::{
+    MySbSubscribersData, ParkedBatch, SubscribeError, SubscribeOptions, UnsubscribeError,
+    UnsubscribeOptions, UnsubscribeOutcome, PARKED_BATCH_TIMEOUT,
+};
 
 pub struct MySbSubscribers {
     subscribers: Arc<Mutex<MySbSubscribersData>>,
+    // Confirmation ids of delivered batches which are not confirmed yet, by topic and queue
+    in_flight_batches: std::sync::Mutex<HashMap<(String, String), HashSet<i64>>>,
+    batch_confirmed: Notify,
+    #[cfg(feature = "encryption")]
+    key_provider: std::sync::RwLock<
+        Option<(
+            Arc<dyn crate::encryption::EncryptionKeyProvider + Send + Sync + 'static>,
+            Arc<dyn rust_extensions::Logger + Send + Sync + 'static>,
+        )>,
+    >,
 }
 
 impl MySbSubscribers {
     pub fn new() -> Self {
         Self {
             subscribers: Arc::new(Mutex::new(MySbSubscribersData::new())),
+            in_flight_batches: std::sync::Mutex::new(HashMap::new()),
+            batch_confirmed: Notify::new(),
+            #[cfg(feature = "encryption")]
+            key_provider: std::sync::RwLock::new(None),
         }
     }
 
+    #[cfg(feature = "encryption")]
+    pub fn set_key_provider(
+        &self,
+        key_provider: Arc<dyn crate::encryption::EncryptionKeyProvider + Send + Sync + 'static>,
+        logger: Arc<dyn rust_extensions::Logger + Send + Sync + 'static>,
+    ) {
+        let mut write_access = self.key_provider.write().unwrap();
+        *write_access = Some((key_provider, logger));
+    }
+
     pub async fn add(
         &self,
-        topic_id: &'static str,
+        topic_id: String,
         queue_id: String,
         callback: Arc<dyn MyServiceBusSubscriberClientCallback + Send + Sync + 'static>,
-    ) {
-        let mut write_access = self.subscribers.lock().await;
-        write_access.add(topic_id, queue_id, callback);
+        options: &SubscribeOptions,
+    ) -> Result<(), SubscribeError> {
+        let key = (topic_id.clone(), queue_id.clone());
+
+        let (replaced, connection, parked) = {
+            let mut write_access = self.subscribers.lock().await;
+            let replaced = write_access.add(topic_id, queue_id, callback.clone(), options)?;
+            let parked = write_access.parked_batches.remove(&key);
+            (replaced, write_access.connection.clone(), parked)
+        };
+
+        // Subscribers added after the connection is established are sent right away.
+        // Replaced one keeps the subscription the broker already has.
+        if !replaced {
+            if let Some(connection) = connection.as_ref() {
+                send_subscribe(connection, callback.as_ref()).await;
+            }
+        }
+
+        if let Some(parked) = parked {
+            let is_current_connection = match connection.as_ref() {
+                Some(connection) => connection.id == parked.connection_id,
+                None => false,
+            };
+
+            if is_current_connection {
+                self.new_messages(
+                    key.0,
+                    key.1,
+                    parked.confirmation_id,
+                    parked.connection_id,
+                    parked.messages,
+                )
+                .await;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Dispatching stops right away. Batches which are being handled are waited for,
+    // then the connection is reestablished only if it is asked for by options.
+    // Without the reconnect batches still delivered to the queue are parked and returned as failed
+    // after PARKED_BATCH_TIMEOUT.
+    pub async fn unsubscribe(
+        &self,
+        topic_id: &str,
+        queue_id: &str,
+        options: &UnsubscribeOptions,
+    ) -> Result<UnsubscribeOutcome, UnsubscribeError> {
+        let (removed, connection) = {
+            let mut write_access = self.subscribers.lock().await;
+            let removed = write_access.remove(topic_id, queue_id);
+            (removed, write_access.connection.clone())
+        };
+
+        if removed.is_none() {
+            return Err(UnsubscribeError::NotSubscribed);
+        }
+
+        let wait = self.wait_for_in_flight_batches(topic_id, queue_id);
+
+        let outcome = match tokio::time::timeout(options.in_flight_timeout, wait).await {
+            Ok(_) => UnsubscribeOutcome::Completed,
+            Err(_) => UnsubscribeOutcome::InFlightTimeout,
+        };
+
+        if options.reconnect {
+            if let Some(connection) = connection {
+                connection.disconnect().await;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn wait_for_in_flight_batches(&self, topic_id: &str, queue_id: &str) {
+        let key = (topic_id.to_string(), queue_id.to_string());
+
+        loop {
+            let batch_confirmed = self.batch_confirmed.notified();
+
+            {
+                let read_access = self.in_flight_batches.lock().unwrap();
+                if !read_access.contains_key(&key) {
+                    return;
+                }
+            }
+
+            batch_confirmed.await;
+        }
+    }
+
+    fn set_batch_delivered(&self, topic_id: &str, queue_id: &str, confirmation_id: i64) {
+        let mut write_access = self.in_flight_batches.lock().unwrap();
+        write_access
+            .entry((topic_id.to_string(), queue_id.to_string()))
+            .or_default()
+            .insert(confirmation_id);
+    }
+
+    fn set_batch_confirmed(&self, topic_id: &str, queue_id: &str, confirmation_id: i64) {
+        {
+            let mut write_access = self.in_flight_batches.lock().unwrap();
+            let key = (topic_id.to_string(), queue_id.to_string());
+
+            if let Some(batches) = write_access.get_mut(&key) {
+                batches.remove(&confirmation_id);
+
+                if batches.is_empty() {
+                    write_access.remove(&key);
+                }
+            }
+        }
+
+        self.batch_confirmed.notify_waiters();
     }
 
     pub async fn new_messages(
@@ -41,11 +184,64 @@ impl MySbSubscribers {
         messages: Vec<MySbMessage>,
     ) {
         let callback = {
-            let read_access = self.subscribers.lock().await;
-            read_access.get_callback(topic_id.as_str(), queue_id.as_str())
+            let mut write_access = self.subscribers.lock().await;
+            let callback = write_access.get_callback(topic_id.as_str(), queue_id.as_str());
+
+            if callback.is_none() {
+                write_access.parked_batches.insert(
+                    (topic_id.clone(), queue_id.clone()),
+                    ParkedBatch {
+                        confirmation_id,
+                        connection_id,
+                        messages,
+                    },
+                );
+
+                self.fail_parked_batch_after_timeout(topic_id, queue_id, confirmation_id);
+                return;
+            }
+
+            callback
         };
 
         if let Some(callback) = callback {
+            self.set_batch_delivered(topic_id.as_str(), queue_id.as_str(), confirmation_id);
+
+            #[cfg(feature = "encryption")]
+            let messages = {
+                let mut messages = messages;
+
+                let key_provider = self.key_provider.read().unwrap().clone();
+
+                if let Some((key_provider, logger)) = key_provider {
+                    crate::encryption::decrypt_messages(
+                        key_provider.as_ref(),
+                        logger.as_ref(),
+                        topic_id.as_str(),
+                        &mut messages,
+                    );
+                }
+
+                messages
+            };
+
+            #[cfg(feature = "opentelemetry")]
+            {
+                use tracing::Instrument;
+
+                let span = crate::trace_context::create_new_messages_span(
+                    topic_id.as_str(),
+                    queue_id.as_str(),
+                    &messages,
+                );
+
+                callback
+                    .new_events(messages, confirmation_id, connection_id)
+                    .instrument(span)
+                    .await;
+            }
+
+            #[cfg(not(feature = "opentelemetry"))]
             callback
                 .new_events(messages, confirmation_id, connection_id)
                 .await;
@@ -77,24 +273,63 @@ impl MySbSubscribers {
         }
 
         for subscriber in self.get_subscribers().await {
-            let packet = TcpContract::Subscribe {
-                topic_id: subscriber.get_topic_id().to_string(),
-                queue_id: subscriber.get_queue_id().to_string(),
-                queue_type: subscriber.get_queue_type(),
-            };
-
-            connection
-                .send_bytes(
-                    packet
-                        .serialize(crate::new_connection_handler::PROTOCOL_VERSION)
-                        .as_slice(),
-                )
-                .await;
+            send_subscribe(&connection, subscriber.as_ref()).await;
         }
     }
     pub async fn disconnect(&self) {
-        let mut write_access = self.subscribers.lock().await;
-        write_access.connection = None;
+        {
+            let mut write_access = self.subscribers.lock().await;
+            write_access.connection = None;
+            write_access.parked_batches.clear();
+        }
+
+        // Broker redelivers unconfirmed batches, so they are not in flight anymore
+        {
+            let mut write_access = self.in_flight_batches.lock().unwrap();
+            write_access.clear();
+        }
+
+        self.batch_confirmed.notify_waiters();
+    }
+
+    fn fail_parked_batch_after_timeout(
+        &self,
+        topic_id: String,
+        queue_id: String,
+        confirmation_id: i64,
+    ) {
+        let subscribers = self.subscribers.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(PARKED_BATCH_TIMEOUT).await;
+
+            let key = (topic_id, queue_id);
+
+            let (parked, connection) = {
+                let mut write_access = subscribers.lock().await;
+
+                // Batch can be handed to a new subscriber or replaced by a batch of a new connection
+                match write_access.parked_batches.get(&key) {
+                    Some(parked) if parked.confirmation_id == confirmation_id => {}
+                    _ => return,
+                }
+
+                let parked = write_access.parked_batches.remove(&key).unwrap();
+                (parked, write_access.connection.clone())
+            };
+
+            if let Some(connection) = connection {
+                if connection.id == parked.connection_id {
+                    connection
+                        .send(TcpContract::AllMessagesConfirmedAsFail {
+                            topic_id: key.0,
+                            queue_id: key.1,
+                            confirmation_id,
+                        })
+                        .await;
+                }
+            }
+        });
     }
 
     fn send_packet(&self, tcp_contract: TcpContract, connection_id: i32) {
@@ -138,6 +373,7 @@ impl MyServiceBusSubscriberClient for MySbSubscribers {
             }
         };
 
+        self.set_batch_confirmed(topic_id, queue_id, confirmation_id);
         self.send_packet(tcp_contract, connection_id);
     }
 
@@ -157,6 +393,26 @@ impl MyServiceBusSubscriberClient for MySbSubscribers {
             delivered,
         };
 
+        self.set_batch_confirmed(topic_id, queue_id, confirmation_id);
         self.send_packet(tcp_contract, connection_id);
     }
 }
+
+async fn send_subscribe(
+    connection: &SocketConnection<TcpContract, MySbTcpSerializer>,
+    subscriber: &(dyn MyServiceBusSubscriberClientCallback + Send + Sync + 'static),
+) {
+    let packet = TcpContract::Subscribe {
+        topic_id: subscriber.get_topic_id().to_string(),
+        queue_id: subscriber.get_queue_id().to_string(),
+        queue_type: subscriber.get_queue_type(),
+    };
+
+    connection
+        .send_bytes(
+            packet
+                .serialize(crate::new_connection_handler::PROTOCOL_VERSION)
+                .as_slice(),
+        )
+        .await;
+}
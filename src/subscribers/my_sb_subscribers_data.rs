@@ -1,15 +1,29 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use my_service_bus_abstractions::MyServiceBusSubscriberClientCallback;
+use my_service_bus_abstractions::{MySbMessage, MyServiceBusSubscriberClientCallback};
 use my_service_bus_tcp_shared::{MySbTcpSerializer, TcpContract};
 use my_tcp_sockets::tcp_connection::SocketConnection;
 
+use super::{SubscribeError, SubscribeOptions};
+
+// Batch delivered to a queue which has no subscriber. It stays unconfirmed, so the broker does not deliver
+// more until it is handed to a new subscriber of the queue or PARKED_BATCH_TIMEOUT passes.
+pub struct ParkedBatch {
+    pub confirmation_id: i64,
+    pub connection_id: i32,
+    pub messages: Vec<MySbMessage>,
+}
+
+// Parked batch is returned to the queue as failed after it, so another instance sharing the queue can get it
+pub const PARKED_BATCH_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct MySbSubscribersData {
     pub subscribers: HashMap<
-        &'static str,
+        String,
         HashMap<String, Arc<dyn MyServiceBusSubscriberClientCallback + Send + Sync + 'static>>,
     >,
     pub connection: Option<Arc<SocketConnection<TcpContract, MySbTcpSerializer>>>,
+    pub parked_batches: HashMap<(String, String), ParkedBatch>,
 }
 
 impl MySbSubscribersData {
@@ -17,29 +31,49 @@ impl MySbSubscribersData {
         Self {
             subscribers: HashMap::new(),
             connection: None,
+            parked_batches: HashMap::new(),
         }
     }
 
+    // Returns true if an existing subscriber is replaced
     pub fn add(
         &mut self,
-        topic_id: &'static str,
+        topic_id: String,
         queue_id: String,
         subscriber_callback: Arc<dyn MyServiceBusSubscriberClientCallback + Sync + Send + 'static>,
-    ) {
-        if !self.subscribers.contains_key(topic_id) {
-            self.subscribers.insert(topic_id, HashMap::new());
+        options: &SubscribeOptions,
+    ) -> Result<bool, SubscribeError> {
+        if queue_id.is_empty() {
+            return Err(SubscribeError::EmptyQueueId { topic_id });
         }
 
-        let by_topic = self.subscribers.get_mut(topic_id).unwrap();
+        let by_topic = self.subscribers.entry(topic_id.clone()).or_default();
 
-        if by_topic.contains_key(queue_id.as_str()) {
-            panic!(
-                "Subscriber for topic:{} and queue:{} is already registered",
-                topic_id, queue_id
-            );
+        let replaced = by_topic.contains_key(queue_id.as_str());
+
+        if replaced && !options.replace_existing {
+            return Err(SubscribeError::AlreadySubscribed { topic_id, queue_id });
         }
 
         by_topic.insert(queue_id, subscriber_callback);
+
+        Ok(replaced)
+    }
+
+    pub fn remove(
+        &mut self,
+        topic_id: &str,
+        queue_id: &str,
+    ) -> Option<Arc<dyn MyServiceBusSubscriberClientCallback + Sync + Send + 'static>> {
+        let by_topic = self.subscribers.get_mut(topic_id)?;
+
+        let result = by_topic.remove(queue_id);
+
+        if by_topic.is_empty() {
+            self.subscribers.remove(topic_id);
+        }
+
+        result
     }
 
     pub fn get_callback(
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use futures::StreamExt;
+use my_service_bus_abstractions::{
+    subscriber::{MySbMessageDeserializer, TopicQueueType},
+    MySbMessage, MyServiceBusSubscriberClientCallback,
+};
+use rust_extensions::Logger;
+
+use super::{confirm_messages, MySbSubscribers};
+
+#[async_trait::async_trait]
+pub trait RawMessageHandler {
+    // Err returns the message to the queue
+    async fn handle_message(&self, message: &MySbMessage) -> Result<(), String>;
+}
+
+#[async_trait::async_trait]
+pub trait MessageHandler<TModel> {
+    // Err returns the message to the queue
+    async fn handle_message(&self, message: TModel) -> Result<(), String>;
+}
+
+// Message which can not be deserialized is failed, so it returns to the queue
+pub struct DeserializingMessageHandler<TModel> {
+    handler: Arc<dyn MessageHandler<TModel> + Send + Sync + 'static>,
+    itm: PhantomData<TModel>,
+}
+
+impl<TModel> DeserializingMessageHandler<TModel> {
+    pub fn new(handler: Arc<dyn MessageHandler<TModel> + Send + Sync + 'static>) -> Self {
+        Self {
+            handler,
+            itm: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<TModel: MySbMessageDeserializer<Item = TModel> + Send + Sync + 'static> RawMessageHandler
+    for DeserializingMessageHandler<TModel>
+{
+    async fn handle_message(&self, message: &MySbMessage) -> Result<(), String> {
+        let model = TModel::deserialize(message.content.as_slice(), &message.headers)
+            .map_err(|err| format!("Message can not be deserialized: {:?}", err))?;
+
+        self.handler.handle_message(model).await
+    }
+}
+
+pub struct ParallelSubscriber {
+    topic_id: String,
+    queue_id: String,
+    queue_type: TopicQueueType,
+    max_parallelism: usize,
+    handler: Arc<dyn RawMessageHandler + Send + Sync + 'static>,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+    client: Arc<MySbSubscribers>,
+}
+
+impl ParallelSubscriber {
+    pub fn new(
+        topic_id: String,
+        queue_id: String,
+        queue_type: TopicQueueType,
+        max_parallelism: usize,
+        handler: Arc<dyn RawMessageHandler + Send + Sync + 'static>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+        client: Arc<MySbSubscribers>,
+    ) -> Self {
+        Self {
+            topic_id,
+            queue_id,
+            queue_type,
+            max_parallelism: max_parallelism.max(1),
+            handler,
+            logger,
+            client,
+        }
+    }
+}
+
+async fn handle_message(
+    handler: Arc<dyn RawMessageHandler + Send + Sync + 'static>,
+    messages: Arc<Vec<MySbMessage>>,
+    index: usize,
+) -> Result<(), String> {
+    let future = async move { handler.handle_message(&messages[index]).await };
+
+    #[cfg(feature = "opentelemetry")]
+    let future = tracing::Instrument::in_current_span(future);
+
+    let result = tokio::spawn(future).await;
+
+    match result {
+        Ok(result) => result,
+        Err(err) => Err(format!("Handler panicked: {:?}", err)),
+    }
+}
+
+#[async_trait::async_trait]
+impl MyServiceBusSubscriberClientCallback for ParallelSubscriber {
+    fn get_topic_id(&self) -> &str {
+        self.topic_id.as_str()
+    }
+
+    fn get_queue_id(&self) -> &str {
+        self.queue_id.as_str()
+    }
+
+    fn get_queue_type(&self) -> TopicQueueType {
+        self.queue_type
+    }
+
+    // Batch is confirmed once every message is handled, delivered ones are reported by id
+    async fn new_events(
+        &self,
+        messages: Vec<MySbMessage>,
+        confirmation_id: i64,
+        connection_id: i32,
+    ) {
+        let handler = self.handler.clone();
+        let logger = self.logger.clone();
+        let client = self.client.clone();
+        let topic_id = self.topic_id.clone();
+        let queue_id = self.queue_id.clone();
+        let max_parallelism = self.max_parallelism;
+
+        let future = async move {
+            let messages_amount = messages.len();
+            let messages = Arc::new(messages);
+
+            let results: Vec<(usize, Result<(), String>)> =
+                futures::stream::iter(0..messages_amount)
+                    .map(|index| {
+                        let handler = handler.clone();
+                        let messages = messages.clone();
+                        async move { (index, handle_message(handler, messages, index).await) }
+                    })
+                    .buffer_unordered(max_parallelism)
+                    .collect()
+                    .await;
+
+            let mut delivered = Vec::with_capacity(messages_amount);
+
+            for (index, result) in results {
+                let message = &messages[index];
+
+                match result {
+                    Ok(_) => delivered.push(message.id),
+                    Err(err) => {
+                        let mut ctx = HashMap::new();
+                        ctx.insert("TopicId".to_string(), topic_id.clone());
+                        ctx.insert("QueueId".to_string(), queue_id.clone());
+                        ctx.insert("MessageId".to_string(), message.id.to_string());
+
+                        logger.write_error(
+                            "ParallelSubscriber".to_string(),
+                            format!("Message is not handled: {}", err),
+                            Some(ctx),
+                        );
+                    }
+                }
+            }
+
+            confirm_messages(
+                &client,
+                topic_id.as_str(),
+                queue_id.as_str(),
+                confirmation_id,
+                connection_id,
+                delivered,
+                messages_amount,
+            );
+        };
+
+        #[cfg(feature = "opentelemetry")]
+        let future = tracing::Instrument::in_current_span(future);
+
+        tokio::spawn(future);
+    }
+}
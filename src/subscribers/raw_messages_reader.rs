@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use my_service_bus_abstractions::{queue_with_intervals::QueueIndexRange, MySbMessage};
+
+// Messages are confirmed as delivered unless they are marked as failed
+pub struct RawMessagesReader {
+    pub topic_id: String,
+    pub queue_id: String,
+    messages: Vec<MySbMessage>,
+    failed: HashSet<i64>,
+}
+
+impl RawMessagesReader {
+    pub fn new(topic_id: String, queue_id: String, messages: Vec<MySbMessage>) -> Self {
+        Self {
+            topic_id,
+            queue_id,
+            messages,
+            failed: HashSet::new(),
+        }
+    }
+
+    pub fn get_messages(&self) -> &[MySbMessage] {
+        self.messages.as_slice()
+    }
+
+    pub fn mark_as_failed(&mut self, message_id: i64) {
+        self.failed.insert(message_id);
+    }
+
+    pub fn mark_all_as_failed(&mut self) {
+        for message in &self.messages {
+            self.failed.insert(message.id);
+        }
+    }
+
+    pub fn get_delivered_ids(&self) -> Vec<i64> {
+        self.messages
+            .iter()
+            .map(|message| message.id)
+            .filter(|id| !self.failed.contains(id))
+            .collect()
+    }
+}
+
+pub fn compile_delivered_ranges(mut delivered: Vec<i64>) -> Vec<QueueIndexRange> {
+    delivered.sort_unstable();
+
+    let mut result: Vec<QueueIndexRange> = Vec::new();
+
+    for id in delivered {
+        if let Some(last) = result.last_mut() {
+            if last.to_id + 1 == id {
+                last.to_id = id;
+                continue;
+            }
+        }
+
+        result.push(QueueIndexRange {
+            from_id: id,
+            to_id: id,
+        });
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile_delivered_ranges;
+
+    fn compile(delivered: Vec<i64>) -> Vec<(i64, i64)> {
+        compile_delivered_ranges(delivered)
+            .into_iter()
+            .map(|range| (range.from_id, range.to_id))
+            .collect()
+    }
+
+    #[test]
+    fn test_sequential_ids_are_one_range() {
+        assert_eq!(vec![(5, 8)], compile(vec![5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_gaps_split_ranges() {
+        assert_eq!(vec![(1, 2), (4, 4), (6, 7)], compile(vec![1, 2, 4, 6, 7]));
+    }
+
+    #[test]
+    fn test_unsorted_ids_are_sorted() {
+        assert_eq!(vec![(1, 3), (10, 10)], compile(vec![10, 3, 1, 2]));
+    }
+
+    #[test]
+    fn test_no_ids_give_no_ranges() {
+        assert!(compile(vec![]).is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::HashMap, sync::Arc};
+
+use my_service_bus_abstractions::{
+    subscriber::TopicQueueType, MySbMessage, MyServiceBusSubscriberClient,
+    MyServiceBusSubscriberClientCallback,
+};
+use rust_extensions::Logger;
+
+use super::{compile_delivered_ranges, MySbSubscribers, RawMessagesReader};
+
+#[async_trait::async_trait]
+pub trait RawSubscriberCallback {
+    async fn handle_messages(&self, messages_reader: &mut RawMessagesReader);
+}
+
+pub struct RawSubscriber {
+    topic_id: String,
+    queue_id: String,
+    queue_type: TopicQueueType,
+    callback: Arc<dyn RawSubscriberCallback + Send + Sync + 'static>,
+    logger: Arc<dyn Logger + Send + Sync + 'static>,
+    client: Arc<MySbSubscribers>,
+}
+
+impl RawSubscriber {
+    pub fn new(
+        topic_id: String,
+        queue_id: String,
+        queue_type: TopicQueueType,
+        callback: Arc<dyn RawSubscriberCallback + Send + Sync + 'static>,
+        logger: Arc<dyn Logger + Send + Sync + 'static>,
+        client: Arc<MySbSubscribers>,
+    ) -> Self {
+        Self {
+            topic_id,
+            queue_id,
+            queue_type,
+            callback,
+            logger,
+            client,
+        }
+    }
+}
+
+// Whole batch is confirmed with one packet when all messages are delivered or all are failed
+pub fn confirm_messages(
+    client: &MySbSubscribers,
+    topic_id: &str,
+    queue_id: &str,
+    confirmation_id: i64,
+    connection_id: i32,
+    delivered: Vec<i64>,
+    messages_amount: usize,
+) {
+    if delivered.len() == messages_amount {
+        client.confirm_delivery(topic_id, queue_id, confirmation_id, connection_id, true);
+    } else if delivered.is_empty() {
+        client.confirm_delivery(topic_id, queue_id, confirmation_id, connection_id, false);
+    } else {
+        client.confirm_some_messages_ok(
+            topic_id,
+            queue_id,
+            confirmation_id,
+            connection_id,
+            compile_delivered_ranges(delivered),
+        );
+    }
+}
+
+#[async_trait::async_trait]
+impl MyServiceBusSubscriberClientCallback for RawSubscriber {
+    fn get_topic_id(&self) -> &str {
+        self.topic_id.as_str()
+    }
+
+    fn get_queue_id(&self) -> &str {
+        self.queue_id.as_str()
+    }
+
+    fn get_queue_type(&self) -> TopicQueueType {
+        self.queue_type
+    }
+
+    // Batch is handled on its own task, so socket reading is not blocked by the callback
+    async fn new_events(
+        &self,
+        messages: Vec<MySbMessage>,
+        confirmation_id: i64,
+        connection_id: i32,
+    ) {
+        let callback = self.callback.clone();
+        let logger = self.logger.clone();
+        let client = self.client.clone();
+        let topic_id = self.topic_id.clone();
+        let queue_id = self.queue_id.clone();
+
+        let reader = RawMessagesReader::new(topic_id.clone(), queue_id.clone(), messages);
+
+        let future = async move {
+            let handle = async move {
+                let mut reader = reader;
+                callback.handle_messages(&mut reader).await;
+                reader
+            };
+
+            #[cfg(feature = "opentelemetry")]
+            let handle = tracing::Instrument::in_current_span(handle);
+
+            let handled = tokio::spawn(handle).await;
+
+            match handled {
+                Ok(reader) => confirm_messages(
+                    &client,
+                    topic_id.as_str(),
+                    queue_id.as_str(),
+                    confirmation_id,
+                    connection_id,
+                    reader.get_delivered_ids(),
+                    reader.get_messages().len(),
+                ),
+                Err(err) => {
+                    let mut ctx = HashMap::new();
+                    ctx.insert("TopicId".to_string(), topic_id.clone());
+                    ctx.insert("QueueId".to_string(), queue_id.clone());
+                    ctx.insert("ConfirmationId".to_string(), confirmation_id.to_string());
+
+                    logger.write_error(
+                        "RawSubscriber".to_string(),
+                        format!("Subscriber panicked while handling batch: {:?}", err),
+                        Some(ctx),
+                    );
+
+                    client.confirm_delivery(
+                        topic_id.as_str(),
+                        queue_id.as_str(),
+                        confirmation_id,
+                        connection_id,
+                        false,
+                    );
+                }
+            }
+        };
+
+        #[cfg(feature = "opentelemetry")]
+        let future = tracing::Instrument::in_current_span(future);
+
+        tokio::spawn(future);
+    }
+}
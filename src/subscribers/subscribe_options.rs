@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct SubscribeOptions {
+    // Existing subscriber of the same topic and queue gets replaced instead of failing with AlreadySubscribed
+    pub replace_existing: bool,
+    // Messages of a batch handled at the same time. Only per message handlers of subscribe_parallel
+    // and subscribe_raw_parallel can run concurrently, batch callbacks fail with MaxParallelismNotSupported.
+    pub max_parallelism: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscribeError {
+    AlreadySubscribed { topic_id: String, queue_id: String },
+    EmptyQueueId { topic_id: String },
+    MaxParallelismNotSupported { topic_id: String, queue_id: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct UnsubscribeOptions {
+    // Max time to wait for batches which are being handled to be confirmed
+    pub in_flight_timeout: Duration,
+    // Protocol has no unsubscribe packet, so by default the broker keeps the subscription till the next reconnect
+    // and batches it delivers are returned as failed after PARKED_BATCH_TIMEOUT. Reconnect drops it right away, but in-flight publishes of every topic fail or wait for a resend
+    // and in-flight batches of every other subscriber are redelivered.
+    pub reconnect: bool,
+}
+
+impl Default for UnsubscribeOptions {
+    fn default() -> Self {
+        Self {
+            in_flight_timeout: Duration::from_secs(30),
+            reconnect: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnsubscribeError {
+    NotSubscribed,
+}
+
+// Subscriber is removed and the reconnect is done in both cases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsubscribeOutcome {
+    Completed,
+    // Some batches were still being handled when in_flight_timeout passed
+    InFlightTimeout,
+}
@@ -4,13 +4,17 @@ use my_service_bus_tcp_shared::MySbTcpSerializer;
 use my_tcp_sockets::ConnectionEvent;
 use rust_extensions::{Logger, StrOrString};
 
-use crate::{publishers::MySbPublishers, subscribers::MySbSubscribers};
+use crate::{
+    publishers::{MySbPublishers, PublishScheduler},
+    subscribers::MySbSubscribers,
+};
 
 pub struct TcpClientData {
     pub app_name: StrOrString<'static>,
     pub app_version: StrOrString<'static>,
     pub client_version: String,
     pub publishers: Arc<MySbPublishers>,
+    pub scheduler: Arc<PublishScheduler>,
     pub subscribers: Arc<MySbSubscribers>,
     pub logger: Arc<dyn Logger + Send + Sync + 'static>,
     pub has_connection: Arc<AtomicBool>,
@@ -31,6 +35,14 @@ impl TcpClientData {
             my_service_bus_tcp_shared::TcpContract::PublishResponse { request_id } => {
                 self.publishers.set_confirmed(request_id).await;
             }
+            my_service_bus_tcp_shared::TcpContract::Reject { message } => {
+                self.logger.write_error(
+                    "MySbTcpClient".to_string(),
+                    format!("Broker rejected the connection: {}", message),
+                    None,
+                );
+                self.publishers.set_rejected(message);
+            }
             my_service_bus_tcp_shared::TcpContract::NewMessages {
                 topic_id,
                 queue_id,
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use my_service_bus_abstractions::{publisher::MessageToPublish, MySbMessage};
+use opentelemetry::{propagation::TextMapPropagator, trace::TraceContextExt};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::publishers::PublishInterceptor;
+
+pub struct TraceContextPublishInterceptor {
+    propagator: TraceContextPropagator,
+}
+
+impl TraceContextPublishInterceptor {
+    pub fn new() -> Self {
+        Self {
+            propagator: TraceContextPropagator::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PublishInterceptor for TraceContextPublishInterceptor {
+    fn get_name(&self) -> &str {
+        "TraceContext"
+    }
+
+    async fn intercept(
+        &self,
+        _topic_id: &str,
+        message: &mut MessageToPublish,
+    ) -> Result<(), String> {
+        let context = tracing::Span::current().context();
+
+        if !context.span().span_context().is_valid() {
+            return Ok(());
+        }
+
+        let headers = message.headers.get_or_insert_with(HashMap::new);
+        self.propagator.inject_context(&context, headers);
+
+        Ok(())
+    }
+}
+
+pub fn create_new_messages_span(
+    topic_id: &str,
+    queue_id: &str,
+    messages: &[MySbMessage],
+) -> tracing::Span {
+    let span = tracing::info_span!(
+        "my_sb.new_messages",
+        topic_id = topic_id,
+        queue_id = queue_id,
+        messages_amount = messages.len()
+    );
+
+    let propagator = TraceContextPropagator::new();
+
+    let mut parent_is_set = false;
+
+    for message in messages {
+        let headers = match message.headers.as_ref() {
+            Some(headers) => headers,
+            None => continue,
+        };
+
+        let context = propagator.extract(headers);
+        let span_context = context.span().span_context().clone();
+
+        if !span_context.is_valid() {
+            continue;
+        }
+
+        if parent_is_set {
+            span.add_link(span_context);
+        } else {
+            span.set_parent(context);
+            parent_is_set = true;
+        }
+    }
+
+    span
+}